@@ -0,0 +1,138 @@
+// The referee as a program, for cg-brutaltester or for two bot commands given on the command line:
+//
+//   referee [flags]                 speaks the brutaltester protocol
+//   referee [flags] "bot a" "bot b" starts both bots itself
+//
+// with the flags [-seed N] [--replay FILE] [--resume FILE --turn N].
+// The seed picks the generated map, a random one is used and printed to stderr without it. It is
+// -seed as brutaltester passes it, --seed is read too.
+// With --replay the game is also written to FILE, see the replay module. With --resume the game
+// goes on from the board of the given turn of a replay written here instead, see Referee::resume.
+// With brutaltester, the tester starts the game with `###Start 2`. The referee sends each
+// player's input as `###Input <player>` and the lines, asks for their line of actions with
+// `###Output <player> 1`, and reads it back. It ends the game with `###End` and the players
+// from first to last, tied players written together: `###End 1 0`, `###End 01`.
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use kotg_core::json::Json;
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::referee::{Bot, GameResult, ProcessBot, Referee};
+use kotg_core::replay::Replay;
+use kotg_core::Rng;
+
+// One player seen through the tester, which talks to the real process
+struct TesterPlayer {
+    player: usize,
+}
+
+impl Bot for TesterPlayer {
+    fn play(&mut self, input: &str) -> String {
+        let mut stdout = io::stdout().lock();
+        let _ = write!(stdout, "###Input {}\n{}###Output {} 1\n", self.player, input, self.player);
+        let _ = stdout.flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => "the tester closed the referee's input".to_string(),
+            Ok(_) => line.trim_end().to_string(),
+        }
+    }
+}
+
+// The value following the flag, written any of the given ways, both taken out of the arguments
+fn take_flag(args: &mut Vec<String>, names: &[&str]) -> Result<Option<String>, String> {
+    let Some(k) = args.iter().position(|arg| names.contains(&arg.as_str())) else { return Ok(None) };
+    if k + 1 == args.len() {
+        return Err(format!("{} needs a value", args[k]));
+    }
+    args.remove(k);
+    Ok(Some(args.remove(k)))
+}
+
+fn ranking(result: &GameResult) -> String {
+    match result.winner() {
+        Some(player) => format!("{} {}", player, 1 - player),
+        None => "01".to_string(),
+    }
+}
+
+fn write_replay(path: Option<&str>, replay: &Replay) {
+    if let Some(Err(err)) = path.map(|path| std::fs::write(path, replay.to_json().to_string())) {
+        eprintln!("referee: cannot write the replay: {}", err);
+    }
+}
+
+fn resume(path: &str, turn: &str) -> Result<Referee, String> {
+    let turn: usize = turn.parse().map_err(|_| format!("--turn {:?} is not a number", turn))?;
+    let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?.parse::<Json>().map_err(|err| err.to_string())?;
+    Ok(Referee::resume(&Replay::recorded_board(&json, turn)?, turn))
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let flags = [&["-seed", "--seed"][..], &["--replay"], &["--resume"], &["--turn"]].map(|names| take_flag(&mut args, names));
+    let (seed, replay_path, resume_path, resume_turn) = match flags {
+        [Ok(seed), Ok(replay_path), Ok(resume_path), Ok(resume_turn)] => (seed, replay_path, resume_path, resume_turn),
+        [Err(err), ..] | [_, Err(err), ..] | [_, _, Err(err), _] | [.., Err(err)] => {
+            eprintln!("referee: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let seed = match seed.map(|seed| seed.parse::<u64>()) {
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => {
+            eprintln!("referee: -seed needs a number");
+            return ExitCode::FAILURE;
+        }
+        None => Rng::from_clock().next_u64(),
+    };
+    eprintln!("referee: seed {}", seed);
+    let referee = match (resume_path, resume_turn) {
+        (None, None) => Referee::new(&MapGenerator::generate(seed)),
+        (Some(path), Some(turn)) => match resume(&path, &turn) {
+            Ok(referee) => referee,
+            Err(err) => {
+                eprintln!("referee: cannot resume from {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => {
+            eprintln!("referee: --resume and --turn go together");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.as_slice() {
+        [] => {
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line).is_err() || line.trim() != "###Start 2" {
+                eprintln!("referee: expected ###Start 2, got {:?}", line.trim());
+                return ExitCode::FAILURE;
+            }
+            let mut replay = Replay::new(["player 0", "player 1"], Some(seed));
+            let result = referee.run_recorded([&mut TesterPlayer { player: 0 }, &mut TesterPlayer { player: 1 }], &mut replay);
+            write_replay(replay_path.as_deref(), &replay);
+            eprintln!("referee: {:?} after {} turns, {} to {}", result.end, result.turns, result.scores[0], result.scores[1]);
+            println!("###End {}", ranking(&result));
+        }
+        commands @ [first, second] => {
+            let spawned = [first, second].map(|command| ProcessBot::spawn(command).map_err(|err| format!("cannot start {:?}: {}", command, err)));
+            let [Ok(mut first), Ok(mut second)] = spawned else {
+                for err in spawned.into_iter().filter_map(Result::err) {
+                    eprintln!("referee: {}", err);
+                }
+                return ExitCode::FAILURE;
+            };
+            let mut replay = Replay::new([commands[0].as_str(), commands[1].as_str()], Some(seed));
+            let result = referee.run_recorded([&mut first, &mut second], &mut replay);
+            write_replay(replay_path.as_deref(), &replay);
+            let winner = result.winner().map_or("draw".to_string(), |player| format!("player {} wins", player));
+            println!("{:?} after {} turns, {} to {}: {}", result.end, result.turns, result.scores[0], result.scores[1], winner);
+        }
+        _ => {
+            eprintln!("referee: usage: referee [-seed N] [--replay FILE] [--resume FILE --turn N] [\"bot a\" \"bot b\"]");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
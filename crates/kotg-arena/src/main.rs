@@ -0,0 +1,167 @@
+// Self-play runner: the greedy strategy against itself on generated maps, one game per seed.
+//
+//   cargo run -p kotg-arena -- [--replays DIR] [--exploration P] [--resume FILE --turn N] [games] [first seed]
+//   cargo run -p kotg-arena -- --tournament [--notes] [games] [first seed]
+//
+// Each game prints its seed, so a surprising one can be replayed alone, and with --replays is
+// written to DIR/seed-<seed>.json to be watched. The bots' logs go to stderr as they would on
+// CodinGame. The run fails when one side wins far more often than the other.
+// Each game's tiles are also counted by region, bands of rows from north to south, and the summary
+// tells for each region how often each side held more of it and how often the winner did, so a
+// front that keeps being lost shows.
+// --resume plays every game on from the board of the given turn of a replay written here, so a
+// position can be played out many times, told apart by the bots' seeds and --exploration. The
+// side that position favors wins more, so the run does not fail on that.
+// --exploration sets Config::exploration for both bots, so games from the same maps take other
+// lines; the seeds still fix them. --tournament plays the presets against each other instead, see
+// tournament.rs.
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use kotg_core::json::Json;
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::referee::{bot_seed, Referee, StrategyBot, REGIONS};
+use kotg_core::replay::Replay;
+use kotg_core::{Config, GameState};
+
+mod tournament;
+
+const DEFAULT_GAMES: u64 = 10;
+// Win differences between the sides past this many standard deviations of a fair coin's
+const SIDE_BIAS_DEVIATIONS: f64 = 3.;
+
+// The same bot on both sides should win about as often from each, a bigger gap means one side
+// plays differently
+fn lopsided(wins: [u64; 2]) -> bool {
+    let decided = (wins[0] + wins[1]) as f64;
+    (wins[0] as f64 - wins[1] as f64).abs() > SIDE_BIAS_DEVIATIONS * decided.sqrt()
+}
+
+// The value following the flag, both removed from the arguments
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(k) if k + 1 < args.len() => {
+            args.remove(k);
+            Ok(Some(args.remove(k)))
+        }
+        Some(_) => Err(format!("{} needs a value", flag)),
+        None => Ok(None),
+    }
+}
+
+// The board of the replay's turn, with the turn
+fn snapshot(path: &str, turn: &str) -> Result<(GameState, usize), String> {
+    let turn: usize = turn.parse().map_err(|_| format!("--turn {:?} is not a number", turn))?;
+    let json = fs::read_to_string(path).map_err(|err| err.to_string())?.parse::<Json>().map_err(|err| err.to_string())?;
+    Ok((Replay::recorded_board(&json, turn)?, turn))
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let tournament = args.iter().any(|arg| arg == "--tournament");
+    let with_notes = args.iter().any(|arg| arg == "--notes");
+    args.retain(|arg| arg != "--tournament" && arg != "--notes");
+    let flags = ["--replays", "--exploration", "--resume", "--turn"].map(|flag| take_flag(&mut args, flag));
+    let (replay_dir, exploration, resume_path, resume_turn) = match flags {
+        [Ok(replay_dir), Ok(exploration), Ok(resume_path), Ok(resume_turn)] => (replay_dir.map(PathBuf::from), exploration, resume_path, resume_turn),
+        [Err(err), ..] | [_, Err(err), ..] | [_, _, Err(err), _] | [.., Err(err)] => {
+            eprintln!("arena: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let snapshot = match (resume_path, resume_turn) {
+        (None, None) => None,
+        (Some(path), Some(turn)) => match snapshot(&path, &turn) {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                eprintln!("arena: cannot resume from {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => {
+            eprintln!("arena: --resume and --turn go together");
+            return ExitCode::FAILURE;
+        }
+    };
+    let exploration = match exploration.map_or(Ok(0.), |p| p.parse::<f64>().ok().filter(|p| (0. ..=1.).contains(p)).ok_or(p)) {
+        Ok(exploration) => exploration,
+        Err(p) => {
+            eprintln!("arena: --exploration {:?} is not a probability", p);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(Err(err)) = replay_dir.as_ref().map(fs::create_dir_all) {
+        eprintln!("arena: cannot create the replay directory: {}", err);
+        return ExitCode::FAILURE;
+    }
+    let parse = |k: usize, default: u64| args.get(k).map_or(Ok(default), |arg| arg.parse::<u64>().map_err(|_| arg.clone()));
+    let (n_games, first_seed) = match (parse(0, DEFAULT_GAMES), parse(1, 0)) {
+        (Ok(n_games), Ok(first_seed)) => (n_games, first_seed),
+        (Err(arg), _) | (_, Err(arg)) => {
+            eprintln!("arena: {:?} is not a number, usage: kotg-arena [--tournament [--notes] | --replays DIR] [--exploration P] [--resume FILE --turn N] [games] [first seed]", arg);
+            return ExitCode::FAILURE;
+        }
+    };
+    if tournament {
+        if replay_dir.is_some() || exploration > 0. || snapshot.is_some() {
+            eprintln!("arena: --tournament plays the presets as they are on generated maps, without --replays, --exploration or --resume");
+            return ExitCode::FAILURE;
+        }
+        return tournament::run(n_games, first_seed, with_notes);
+    }
+    if with_notes {
+        eprintln!("arena: --notes goes with --tournament");
+        return ExitCode::FAILURE;
+    }
+    let mut wins = [0; 2];
+    let mut draws = 0;
+    // For each region: the games player 0 held more of it, player 1, and the winner
+    let mut regions = [[0; 3]; REGIONS.len()];
+    for seed in first_seed..first_seed + n_games {
+        let (start, referee) = match &snapshot {
+            Some((board, turn)) => (board.clone(), Referee::resume(board, *turn)),
+            None => {
+                let start = MapGenerator::generate(seed);
+                let referee = Referee::new(&start);
+                (start, referee)
+            }
+        };
+        let mut bots = [0, 1].map(|player| StrategyBot::new(Config { seed: Some(bot_seed(seed, player)), exploration, ..Config::default() }));
+        let [first, second] = &mut bots;
+        let mut replay = Replay::new(["greedy", "greedy"], Some(seed));
+        let result = referee.run_recorded([first, second], &mut replay);
+        if let Some(dir) = &replay_dir {
+            let path = dir.join(format!("seed-{}.json", seed));
+            if let Err(err) = fs::write(&path, replay.to_json().to_string()) {
+                eprintln!("arena: cannot write {}: {}", path.display(), err);
+            }
+        }
+        match result.winner() {
+            Some(player) => wins[player] += 1,
+            None => draws += 1,
+        }
+        for (counts, leader) in regions.iter_mut().zip(result.region_leaders()) {
+            if let Some(player) = leader {
+                counts[player] += 1;
+                counts[2] += (result.winner() == leader) as u64;
+            }
+        }
+        let by_region: Vec<String> = result.region_scores().iter().map(|scores| format!("{}-{}", scores[0], scores[1])).collect();
+        let degraded = bots.iter().map(StrategyBot::degraded_turns).collect::<Vec<usize>>();
+        let health = if degraded.iter().any(|&n| n > 0) { format!(", degraded turns {} and {}", degraded[0], degraded[1]) } else { String::new() };
+        println!(
+            "seed {} {}x{}: {:?} after {} turns, {} to {}, by region {}{}",
+            seed, start.width(), start.height(), result.end, result.turns, result.scores[0], result.scores[1], by_region.join(" "), health,
+        );
+    }
+    println!("{} games: player 0 won {}, player 1 won {}, {} draws", n_games, wins[0], wins[1], draws);
+    for (region, [first, second, winner]) in REGIONS.iter().zip(regions) {
+        println!("{}: held by player 0 {}, player 1 {}, even {}, the winner held it in {}", region, first, second, n_games - first - second, winner);
+    }
+    if snapshot.is_none() && lopsided(wins) {
+        println!("lopsided: one side wins far more often");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
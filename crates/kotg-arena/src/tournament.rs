@@ -0,0 +1,184 @@
+// Round robin between the referee's presets, each pair playing every map from both sides:
+//
+//   cargo run --release -p kotg-arena -- --tournament [games] [first seed]
+//
+// Prints each preset's share of the points against each other one, then the Elo ratings that fit
+// those results best, then each preset's share of the REGIONS held at the end of its games. Pairings already played are read back from CACHE_PATH, found by the two
+// presets' fingerprints, the maps and kotg-core's build: a rerun only plays new or changed
+// presets, and everything again once the strategy changed.
+// With --notes each preset's bot keeps notes on each opponent across the games of their series
+// and across runs, in NOTES_PATH: the defense margin it ended on, which the next game starts
+// from, and the opponent's first recycler turn and opening, printed at the end. Results then
+// depend on the notes a run started with, so the pairing cache is neither read nor written.
+use std::collections::HashMap;
+use std::fs;
+use std::process::ExitCode;
+
+use kotg_core::referee::{duel, duel_with_notes, presets, Duel, OpponentNotes, CORE_BUILD, REGIONS};
+
+// From the workspace root, next to the build that played them
+const CACHE_PATH: &str = "target/tournament.txt";
+const NOTES_PATH: &str = "target/opponent-notes.txt";
+const ELO_BASE: f64 = 1500.;
+const ELO_ITERATIONS: usize = 1000;
+const ELO_STEP: f64 = 16.;
+
+// The two fingerprints of a pairing with what each scored over the run's maps
+type Pairing = ((u32, u32), Duel);
+
+fn cache_line(build: &str, first_seed: u64, n_games: u64, ((a, b), duel): Pairing) -> String {
+    let points: Vec<String> = [duel.points].iter().chain(duel.region_points.iter()).flat_map(|points| points.map(|points| points.to_string())).collect();
+    format!("{} {} {} {:08x} {:08x} {}\n", build, first_seed, n_games, a, b, points.join(" "))
+}
+
+// The same duel told from the other Config's side
+fn swapped(duel: Duel) -> Duel {
+    let swap = |[a, b]: [f64; 2]| [b, a];
+    Duel { points: swap(duel.points), region_points: duel.region_points.map(swap) }
+}
+
+fn add(total: Duel, duel: Duel) -> Duel {
+    let add = |a: [f64; 2], b: [f64; 2]| [a[0] + b[0], a[1] + b[1]];
+    Duel { points: add(total.points, duel.points), region_points: std::array::from_fn(|r| add(total.region_points[r], duel.region_points[r])) }
+}
+
+// The pairings cached by this build for these maps
+fn read_cache(first_seed: u64, n_games: u64) -> HashMap<(u32, u32), Duel> {
+    let text = fs::read_to_string(CACHE_PATH).unwrap_or_default();
+    let parse = |line: &str| -> Option<Pairing> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (&[build, first, games, a, b], points) = fields.split_first_chunk::<5>()?;
+        if build != CORE_BUILD || first.parse() != Ok(first_seed) || games.parse() != Ok(n_games) || points.len() != 2 * (1 + REGIONS.len()) {
+            return None;
+        }
+        let fingerprints = (u32::from_str_radix(a, 16).ok()?, u32::from_str_radix(b, 16).ok()?);
+        let points: Vec<f64> = points.iter().map(|points| points.parse().ok()).collect::<Option<_>>()?;
+        let pair = |k: usize| [points[2 * k], points[2 * k + 1]];
+        Some((fingerprints, Duel { points: pair(0), region_points: std::array::from_fn(|r| pair(r + 1)) }))
+    };
+    text.lines().filter_map(parse).collect()
+}
+
+// Each preset's notes on each opponent, by the two fingerprints, the noting preset's first
+fn read_notes() -> HashMap<(u32, u32), OpponentNotes> {
+    let text = fs::read_to_string(NOTES_PATH).unwrap_or_default();
+    let parse = |line: &str| -> Option<((u32, u32), OpponentNotes)> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let &[observer, opponent, ref counts @ ..] = fields.as_slice() else { return None };
+        let counts: Vec<u32> = counts.iter().map(|count| count.parse().ok()).collect::<Option<_>>()?;
+        let &[games, margin_total, build_games, first_build_total, opening_moves, opening_forward] = counts.as_slice() else { return None };
+        let notes = OpponentNotes { games, margin_total: margin_total as i32, build_games, first_build_total, opening_moves, opening_forward };
+        Some(((u32::from_str_radix(observer, 16).ok()?, u32::from_str_radix(opponent, 16).ok()?), notes))
+    };
+    text.lines().filter_map(parse).collect()
+}
+
+fn notes_line(((observer, opponent), notes): ((u32, u32), OpponentNotes)) -> String {
+    let OpponentNotes { games, margin_total, build_games, first_build_total, opening_moves, opening_forward } = notes;
+    format!("{:08x} {:08x} {} {} {} {} {} {}\n", observer, opponent, games, margin_total, build_games, first_build_total, opening_moves, opening_forward)
+}
+
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1. / (1. + 10f64.powf((opponent - rating) / 400.))
+}
+
+// Ratings whose expected scores come closest to the points, averaging ELO_BASE. A preset that
+// won every game has no finite rating and only ends up far ahead.
+fn elo(points: &[Vec<f64>], games: f64) -> Vec<f64> {
+    let n = points.len();
+    let mut ratings = vec![ELO_BASE; n];
+    for _ in 0..ELO_ITERATIONS {
+        let gaps: Vec<f64> = (0..n)
+            .map(|i| (0..n).filter(|&j| j != i).map(|j| points[i][j] / games - expected_score(ratings[i], ratings[j])).sum::<f64>())
+            .collect();
+        for (rating, gap) in ratings.iter_mut().zip(gaps) {
+            *rating += ELO_STEP * gap;
+        }
+        let shift = ELO_BASE - ratings.iter().sum::<f64>() / n as f64;
+        ratings.iter_mut().for_each(|rating| *rating += shift);
+    }
+    ratings
+}
+
+pub fn run(n_games: u64, first_seed: u64, with_notes: bool) -> ExitCode {
+    let presets = presets();
+    let fingerprints: Vec<u32> = presets.iter().map(|(_, config)| config.fingerprint()).collect();
+    let mut cache = if with_notes { HashMap::new() } else { read_cache(first_seed, n_games) };
+    let mut notes = if with_notes { read_notes() } else { HashMap::new() };
+    let n = presets.len();
+    let mut points = vec![vec![0.; n]; n];
+    let mut region_points = vec![[0.; REGIONS.len()]; n];
+    let mut n_played = 0;
+    for i in 0..n {
+        for j in i + 1..n {
+            let key = (fingerprints[i], fingerprints[j]);
+            let pair = match (cache.get(&key), cache.get(&(key.1, key.0))) {
+                _ if with_notes => {
+                    let mut pair_notes = [notes.get(&key).copied().unwrap_or_default(), notes.get(&(key.1, key.0)).copied().unwrap_or_default()];
+                    let pair = (first_seed..first_seed + n_games).map(|seed| duel_with_notes([presets[i].1, presets[j].1], seed, &mut pair_notes)).fold(Duel::default(), add);
+                    notes.insert(key, pair_notes[0]);
+                    notes.insert((key.1, key.0), pair_notes[1]);
+                    n_played += 1;
+                    pair
+                }
+                (Some(&pair), _) => pair,
+                (None, Some(&pair)) => swapped(pair),
+                (None, None) => {
+                    let pair = (first_seed..first_seed + n_games).map(|seed| duel([presets[i].1, presets[j].1], seed)).fold(Duel::default(), add);
+                    n_played += 1;
+                    cache.insert(key, pair);
+                    pair
+                }
+            };
+            points[i][j] = pair.points[0];
+            points[j][i] = pair.points[1];
+            for (region, [a, b]) in pair.region_points.into_iter().enumerate() {
+                region_points[i][region] += a;
+                region_points[j][region] += b;
+            }
+        }
+    }
+    let (path, text): (&str, String) = if with_notes {
+        (NOTES_PATH, notes.iter().map(|(&key, &opponent)| notes_line((key, opponent))).collect())
+    } else {
+        (CACHE_PATH, cache.into_iter().map(|pairing| cache_line(CORE_BUILD, first_seed, n_games, pairing)).collect())
+    };
+    if let Err(err) = fs::write(path, text) {
+        eprintln!("arena: cannot write {}: {}", path, err);
+    }
+    let games = (2 * n_games) as f64;
+    println!("{} presets, {} pairings played, {} cached, {} games each", n, n_played, n * (n - 1) / 2 - n_played, 2 * n_games);
+    let width = presets.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(4);
+    let header: String = presets.iter().map(|(name, _)| format!(" {:>width$}", name, width = width)).collect();
+    println!("{:width$}{}", "", header, width = width);
+    for (i, (name, _)) in presets.iter().enumerate() {
+        let row: String = (0..n)
+            .map(|j| if i == j { format!(" {:>width$}", "-", width = width) } else { format!(" {:>width$.0}%", 100. * points[i][j] / games, width = width - 1) })
+            .collect();
+        println!("{:width$}{}", name, row, width = width);
+    }
+    let ratings = elo(&points, games);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| ratings[b].total_cmp(&ratings[a]));
+    for k in order {
+        println!("elo {:>5.0} {} c{:08x}", ratings[k], presets[k].0, fingerprints[k]);
+    }
+    let header: String = REGIONS.iter().map(|region| format!(" {:>width$}", region, width = width)).collect();
+    println!("{:width$}{}", "regions", header, width = width);
+    for (i, (name, _)) in presets.iter().enumerate() {
+        let row: String = region_points[i].iter().map(|points| format!(" {:>width$.0}%", 100. * points / (games * (n - 1) as f64), width = width - 1)).collect();
+        println!("{:width$}{}", name, row, width = width);
+    }
+    for (i, (name, _)) in presets.iter().enumerate().filter(|_| with_notes) {
+        for (j, (opponent, _)) in presets.iter().enumerate().filter(|&(j, _)| j != i) {
+            let Some(noted) = notes.get(&(fingerprints[i], fingerprints[j])) else { continue };
+            let first_build = noted.first_build_turn().map_or_else(|| "never".to_string(), |turn| format!("on turn {:.1}", turn));
+            let forward = noted.opening_forward_share().map_or_else(|| "-".to_string(), |share| format!("{:.0}%", 100. * share));
+            println!(
+                "notes {:width$} on {:width$}: {} games, margin {}, first recycler {}, opening moves toward it {}",
+                name, opponent, noted.games, noted.margin().unwrap_or(0), first_build, forward, width = width,
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,177 @@
+// Flattens kotg-core and the bot's main.rs into the single file CodinGame accepts. The library
+// becomes an inline `mod kotg_core` so main.rs compiles unchanged, every `mod x;` is replaced by
+// the file's contents, and code only built for offline tools is left out, as are comment lines
+// and indentation.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_OUTPUT: &str = "submission.rs";
+// CodinGame rejects longer code
+const MAX_CHARS: usize = 100_000;
+// Features the submitted build never enables: items behind them are removed
+const OFFLINE_FEATURES: [&str; 2] = ["instrumentation", "internal-docs"];
+// Library modules only the offline tools use
+const OFFLINE_MODULES: [&str; 5] = ["json", "mapgen", "referee", "replay", "scenario"];
+
+fn core_src() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../kotg-core/src")
+}
+
+// Byte offset just past the item or statement starting at `start`: the first `;` outside any
+// brackets, or the `}` closing its first top-level block, with a `;` right after it
+fn item_end(src: &str, start: usize) -> usize {
+    let bytes = src.as_bytes();
+    let mut depth = 0;
+    let mut k = start;
+    while k < bytes.len() {
+        match bytes[k] {
+            b'/' if bytes.get(k + 1) == Some(&b'/') => {
+                k = src[k..].find('\n').map_or(bytes.len(), |n| k + n);
+                continue;
+            }
+            b'/' if bytes.get(k + 1) == Some(&b'*') => {
+                k = src[k + 2..].find("*/").map_or(bytes.len(), |n| k + 2 + n + 2);
+                continue;
+            }
+            b'"' => {
+                k += 1;
+                while k < bytes.len() && bytes[k] != b'"' {
+                    k += if bytes[k] == b'\\' { 2 } else { 1 };
+                }
+            }
+            // A char literal, as opposed to a lifetime, closes within a few bytes
+            b'\'' => {
+                let close = if bytes.get(k + 1) == Some(&b'\\') { src[k + 2..].find('\'').map(|n| k + 2 + n) } else { Some(k + 2) };
+                if let Some(close) = close.filter(|&close| bytes.get(close) == Some(&b'\'')) {
+                    k = close;
+                }
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let rest = &src[k + 1..];
+                    let trimmed = rest.trim_start();
+                    return if trimmed.starts_with(';') { k + 1 + (rest.len() - trimmed.len()) + 1 } else { k + 1 };
+                }
+            }
+            b';' if depth == 0 => return k + 1,
+            _ => (),
+        }
+        k += 1;
+    }
+    bytes.len()
+}
+
+fn is_offline_cfg(attribute: &str) -> bool {
+    attribute == "#[cfg(test)]"
+        || OFFLINE_FEATURES.iter().any(|feature| attribute == format!("#[cfg(feature = \"{}\")]", feature))
+}
+
+fn mentions_offline_feature(attribute: &str) -> bool {
+    OFFLINE_FEATURES.iter().any(|feature| attribute.contains(&format!("feature = \"{}\"", feature)))
+}
+
+// Removes test code and items behind offline features, with the attributes that select them
+fn strip_offline(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut k = 0;
+    while k < src.len() {
+        let line_end = src[k..].find('\n').map_or(src.len(), |n| k + n + 1);
+        let attribute = src[k..line_end].trim();
+        if is_offline_cfg(attribute) {
+            k = item_end(src, line_end);
+            // The rest of the item's last line
+            k = src[k..].find('\n').map_or(src.len(), |n| k + n + 1);
+            continue;
+        }
+        // Only doc attributes depend on them otherwise, dropping those changes nothing
+        if attribute.starts_with("#[cfg") && mentions_offline_feature(attribute) {
+            k = line_end;
+            continue;
+        }
+        out.push_str(&src[k..line_end]);
+        k = line_end;
+    }
+    out
+}
+
+// Comments and indentation take a good part of the length limit and nobody reads the submitted
+// copy. No string literal in the crates spans lines, so dropping indentation changes no text.
+fn strip_comments_and_indentation(src: &str) -> String {
+    src.lines()
+        .map(str::trim_start)
+        .filter(|line| !line.starts_with("//"))
+        .flat_map(|line| [line, "\n"])
+        .collect()
+}
+
+// The file with each `mod x;` replaced by x's file, read from `dir` and its subdirectories
+fn inline_modules(path: &Path, dir: &Path, root: bool) -> Result<String, String> {
+    let src = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let mut out = String::with_capacity(src.len());
+    for line in src.lines() {
+        let trimmed = line.trim();
+        let declaration = trimmed.strip_prefix("pub ").unwrap_or(trimmed).strip_prefix("mod ").and_then(|rest| rest.strip_suffix(';'));
+        let Some(name) = declaration.filter(|name| name.chars().all(|c| c.is_alphanumeric() || c == '_')) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        if root && OFFLINE_MODULES.contains(&name) {
+            continue;
+        }
+        let flat = dir.join(format!("{}.rs", name));
+        let module_path = if flat.exists() { flat } else { dir.join(name).join("mod.rs") };
+        let contents = inline_modules(&module_path, &dir.join(name), false)?;
+        out.push_str(&line[..line.len() - 1]);
+        out.push_str(" {\n");
+        out.push_str(&contents);
+        out.push_str("}\n");
+    }
+    Ok(out)
+}
+
+fn bundle() -> Result<String, String> {
+    let main_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/main.rs");
+    let main = fs::read_to_string(&main_path).map_err(|err| format!("cannot read {}: {}", main_path.display(), err))?;
+    let core = inline_modules(&core_src().join("lib.rs"), &core_src(), true)?;
+    // Library paths are now relative to the module it is inlined in
+    let core = strip_offline(&core).replace("crate::", "crate::kotg_core::");
+    // The build script does not run on CodinGame, the bundle time stands in for the build time
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let core = core.replace("option_env!(\"BUILD_TIMESTAMP\")", &format!("Some(\"{}\")", timestamp));
+    let mut out = String::new();
+    out.push_str("// Generated by `cargo run --bin bundle` from kotg-bot and kotg-core, edit those instead\n");
+    out.push_str(&strip_comments_and_indentation(&strip_offline(&main)));
+    out.push_str("\n#[allow(dead_code, unused_imports)]\nmod kotg_core {\n");
+    out.push_str(&strip_comments_and_indentation(&core));
+    out.push_str("}\n");
+    Ok(out)
+}
+
+// Writes the bundle to the path given as argument, or submission.rs
+fn main() -> ExitCode {
+    let output = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_OUTPUT.to_string());
+    let bundled = match bundle() {
+        Ok(bundled) => bundled,
+        Err(err) => {
+            eprintln!("bundle: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = fs::write(&output, &bundled) {
+        eprintln!("bundle: cannot write {}: {}", output, err);
+        return ExitCode::FAILURE;
+    }
+    let n_chars = bundled.chars().count();
+    println!("{}: {} chars", output, n_chars);
+    if n_chars > MAX_CHARS {
+        eprintln!("bundle: over CodinGame's {} chars limit", MAX_CHARS);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
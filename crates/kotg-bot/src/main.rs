@@ -0,0 +1,95 @@
+use std::time::Instant;
+
+use kotg_core::ascii;
+use kotg_core::health::{self, BotError};
+use kotg_core::hooks::{DesyncDetector, Hooks};
+use kotg_core::io::{self, InputStatus};
+use kotg_core::{Action, ActionSet, Config, GameState, GreedyOutsideStrategy, Rng, Strategy};
+
+// CodinGame's response time limits, the first turn gets longer
+const FIRST_TURN_BUDGET_MS: u128 = 1000;
+const TURN_BUDGET_MS: u128 = 50;
+// Past this into a turn's planning, the analyses the planner can do without are skipped
+const ANALYSIS_BUDGET_MS: u128 = 30;
+const SEED_VAR: &str = "KOTG_SEED";
+
+// Every random choice of the game comes from this seed: `--seed N`, else KOTG_SEED, else the clock
+fn seed() -> Result<u64, String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let given = match args.iter().position(|arg| arg == "--seed") {
+        Some(k) => Some(args.get(k + 1).cloned().ok_or("--seed needs a value")?),
+        None => std::env::var(SEED_VAR).ok(),
+    };
+    match given {
+        Some(seed) => seed.trim().parse().map_err(|_| format!("seed {:?} is not a number", seed)),
+        None => Ok(Rng::from_clock().next_u64()),
+    }
+}
+
+fn main() {
+    let seed = match seed() {
+        Ok(seed) => seed,
+        Err(err) => {
+            eprintln!("cannot start: {}", err);
+            return;
+        }
+    };
+    // The real referee; offline tools feed frames from any other BufRead
+    let mut input = std::io::stdin().lock();
+    let mut game = match io::read_game(&mut input) {
+        Ok(config) => GameState::new(&config),
+        Err(err) => {
+            eprintln!("cannot start: {}", BotError::from(err));
+            return;
+        }
+    };
+    let mut strategy: Box<dyn Strategy> = Box::new(GreedyOutsideStrategy::new(&game, Config { seed: Some(seed), analysis_budget_ms: Some(ANALYSIS_BUDGET_MS), ..Config::default() }));
+    let mut hooks = Hooks::default();
+    // Cheap enough for the ladder, where it is the only way to catch the simulator going wrong
+    hooks.subscribe(Box::new(DesyncDetector::default()));
+    #[cfg(feature = "instrumentation")]
+    hooks.subscribe(Box::new(kotg_core::hooks::TurnLogger));
+    let mut degraded_turns = 0;
+    loop {
+        match io::read_turn(&mut input, &mut game) {
+            InputStatus::Ready => (),
+            InputStatus::Skipped => {
+                health::record_fallback("WAIT on garbled turn");
+                io::print_actions(&[Action::Wait]);
+                degraded_turns += 1;
+                eprintln!("HEALTH: turn {} {}, {} degraded so far", game.turn, health::take(), degraded_turns);
+                continue;
+            }
+            InputStatus::Closed => break,
+        }
+        // The referee's clock starts once the whole turn has been sent
+        let start = Instant::now();
+        if game.turn == 1 {
+            // CodinGame replays hold no input, the replay tool rebuilds it from this board
+            eprintln!("BOARD: {}", ascii::format_board(&game));
+            // Passing it back with --seed plays the same game again from the same frames
+            eprintln!("SEED: {}", seed);
+            eprintln!("SIDE: {:?}", game.my_side);
+        }
+        hooks.turn_start(&game);
+        hooks.state_diff(&game, &game.changed);
+        // Conflicting actions are a planner bug, fixed here so the referee never sees them
+        let mut action_set = ActionSet::new(strategy.plan(&game));
+        for conflict in action_set.resolve(&game) {
+            health::report(conflict.into());
+        }
+        let actions = action_set.into_actions();
+        hooks.actions_chosen(&game, &actions);
+        io::print_actions(&actions);
+        let elapsed_ms = start.elapsed().as_millis();
+        let budget_ms = if game.turn == 1 { FIRST_TURN_BUDGET_MS } else { TURN_BUDGET_MS };
+        if elapsed_ms > budget_ms {
+            health::report(BotError::Timeout { elapsed_ms, budget_ms });
+        }
+        let turn_health = health::take();
+        if !turn_health.is_ok() {
+            degraded_turns += 1;
+        }
+        eprintln!("HEALTH: turn {} {}, {} degraded so far", game.turn, turn_health, degraded_turns);
+    }
+}
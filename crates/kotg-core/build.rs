@@ -0,0 +1,10 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={timestamp}");
+    println!("cargo:rerun-if-changed=src");
+}
@@ -0,0 +1,30 @@
+// Any bytes as the bots' output lines, two per turn, parsed with parse_actions and played through
+// Referee::step on a generated map. Neither may panic: a malformed line is an error, an action
+// the board does not allow is turned away.
+//
+//   cargo +nightly fuzz run actions
+//
+// The first byte picks the map among a few seeds, so the lines meet boards of several sizes. As in
+// the frames target, what the health module collected is taken after each input.
+#![no_main]
+
+use kotg_core::health;
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::parse_actions;
+use kotg_core::referee::Referee;
+use libfuzzer_sys::fuzz_target;
+
+const SEEDS: u64 = 8;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&seed, lines)) = data.split_first() else { return };
+    let text = String::from_utf8_lossy(lines);
+    let mut outputs = text.lines();
+    let mut referee = Referee::new(&MapGenerator::generate(seed as u64 % SEEDS));
+    while let (Some(first), Some(second)) = (outputs.next(), outputs.next()) {
+        let _ = parse_actions(first);
+        let _ = parse_actions(second);
+        referee.step([first, second]);
+    }
+    health::take();
+});
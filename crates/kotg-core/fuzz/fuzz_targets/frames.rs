@@ -0,0 +1,26 @@
+// Any bytes as the referee's input, read with io::read_game and io::read_turn until it runs out.
+// Reading must answer with errors and never panic.
+//
+//   cargo +nightly fuzz run frames
+//
+// cargo-fuzz builds with debug assertions, which also catch overflows the optimized build on
+// CodinGame would wrap. The reader turns away tile lines no referee sends, so a frame that trips
+// a broken invariant (see debug_invariant) is a finding too. What reading reports goes to the
+// health module as in the bot, and is taken after each input as the bot's turn loop does.
+#![no_main]
+
+use std::io::Cursor;
+
+use kotg_core::health;
+use kotg_core::io::{self, InputStatus};
+use kotg_core::GameState;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Cursor::new(data);
+    if let Ok(config) = io::read_game(&mut input) {
+        let mut game = GameState::new(&config);
+        while !matches!(io::read_turn(&mut input, &mut game), InputStatus::Closed) {}
+    }
+    health::take();
+});
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use crate::actions::{Action, Amount};
+use crate::game::{GameState, Owner};
+use crate::grid::Grid;
+use crate::pos::Pos;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Conflict {
+    // Recyclers go up before spawns land, a spawn on a tile being built is lost
+    BuildAndSpawn { at: Pos },
+    // Moves out of one tile ordering more units than stand on it
+    OverCommitted { from: Pos, ordered: i32, available: i32 },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BuildAndSpawn { at } => write!(f, "build and spawn on {:?}", at),
+            Self::OverCommitted { from, ordered, available } =>
+                write!(f, "{} units moved from {:?} holding {}", ordered, from, available),
+        }
+    }
+}
+
+// One turn's actions in priority order: an earlier move keeps its units over a later one,
+// and a build always wins over a spawn on its tile
+#[derive(Debug, Default)]
+pub struct ActionSet {
+    actions: Vec<Action>,
+}
+
+// My units on each tile at the start of the turn, the only ones that can move
+fn movable_units(game: &GameState) -> Grid<i32> {
+    Grid::from_fn(game.width(), game.height(), |pos| {
+        let loc = game.loc(pos);
+        if loc.owner == Owner::Me { loc.units } else { 0 }
+    })
+}
+
+fn built_tiles(actions: &[Action]) -> Vec<Pos> {
+    actions
+        .iter()
+        .filter_map(|action| match *action {
+            Action::Build { at } => Some(at),
+            _ => None,
+        })
+        .collect()
+}
+
+impl ActionSet {
+    pub fn new(actions: Vec<Action>) -> Self {
+        ActionSet { actions }
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub fn into_actions(self) -> Vec<Action> {
+        self.actions
+    }
+
+    // Each conflicting tile once, in the order its first action appears
+    pub fn conflicts(&self, game: &GameState) -> Vec<Conflict> {
+        let built = built_tiles(&self.actions);
+        let available = movable_units(game);
+        let mut ordered = Grid::new(game.width(), game.height(), 0);
+        let mut conflicts = Vec::new();
+        for action in self.actions.iter() {
+            match *action {
+                Action::Spawn { at, .. } if built.contains(&at) => {
+                    let conflict = Conflict::BuildAndSpawn { at };
+                    if !conflicts.contains(&conflict) {
+                        conflicts.push(conflict);
+                    }
+                }
+                Action::Move { amount, from, .. } => {
+                    if let Some(ordered) = ordered.get_mut(from) {
+                        *ordered += amount.get();
+                    }
+                }
+                _ => (),
+            }
+        }
+        let mut sources: Vec<Pos> = Vec::new();
+        for action in self.actions.iter() {
+            if let Action::Move { from, .. } = *action {
+                if !sources.contains(&from) {
+                    sources.push(from);
+                }
+            }
+        }
+        for from in sources {
+            let (Some(&ordered), Some(&available)) = (ordered.get(from), available.get(from)) else {
+                continue;
+            };
+            if ordered > available {
+                conflicts.push(Conflict::OverCommitted { from, ordered, available });
+            }
+        }
+        conflicts
+    }
+
+    // Drops spawns on built tiles and trims the moves that find their source already emptied,
+    // returning the conflicts that were fixed
+    pub fn resolve(&mut self, game: &GameState) -> Vec<Conflict> {
+        let conflicts = self.conflicts(game);
+        if conflicts.is_empty() {
+            return conflicts;
+        }
+        let built = built_tiles(&self.actions);
+        let mut left = movable_units(game);
+        let mut resolved = Vec::with_capacity(self.actions.len());
+        for action in self.actions.drain(..) {
+            match action {
+                Action::Spawn { at, .. } if built.contains(&at) => (),
+                Action::Move { amount, from, to } => match left.get_mut(from) {
+                    Some(left) => {
+                        let take = amount.get().min(*left);
+                        *left -= take;
+                        if let Some(amount) = Amount::new(take) {
+                            resolved.push(Action::Move { amount, from, to });
+                        }
+                    }
+                    // Out of the map, left for validation to reject
+                    None => resolved.push(action),
+                },
+                _ => resolved.push(action),
+            }
+        }
+        self.actions = resolved;
+        conflicts
+    }
+}
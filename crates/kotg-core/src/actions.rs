@@ -0,0 +1,159 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::pos::Pos;
+
+// A unit count that is always positive, so MOVE 0 / SPAWN 0 cannot be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount(i32);
+
+impl Amount {
+    pub const ONE: Amount = Amount(1);
+
+    pub fn new(n: i32) -> Option<Self> {
+        if n > 0 { Some(Amount(n)) } else { None }
+    }
+
+    pub fn get(self) -> i32 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Move { amount: Amount, from: Pos, to: Pos },
+    Build { at: Pos },
+    Spawn { amount: Amount, at: Pos },
+    Wait,
+    Message { text: String },
+}
+
+impl Action {
+    // The same action on the board turned around its center, its tiles being on the board
+    pub fn mirrored(&self, width: usize, height: usize) -> Action {
+        let mirror = |pos: Pos| pos.mirrored(width, height);
+        match *self {
+            Action::Move { amount, from, to } => Action::Move { amount, from: mirror(from), to: mirror(to) },
+            Action::Build { at } => Action::Build { at: mirror(at) },
+            Action::Spawn { amount, at } => Action::Spawn { amount, at: mirror(at) },
+            Action::Wait | Action::Message { .. } => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Move { amount, from, to } =>
+                write!(f, "MOVE {} {} {} {} {}", amount.get(), from.x(), from.y(), to.x(), to.y()),
+            Self::Build { at } =>
+                write!(f, "BUILD {} {}", at.x(), at.y()),
+            Self::Spawn { amount, at } =>
+                write!(f, "SPAWN {} {} {}", amount.get(), at.x(), at.y()),
+            Self::Wait =>
+                write!(f, "WAIT"),
+            Self::Message { text } =>
+                write!(f, "MESSAGE {text}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseActionError(String);
+
+impl fmt::Display for ParseActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bad action {:?}", self.0)
+    }
+}
+
+// The inverse of Display, coordinates in the engine's x y order
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || ParseActionError(s.to_string());
+        let (keyword, rest) = s.split_once(' ').unwrap_or((s, ""));
+        if keyword == "MESSAGE" {
+            return Ok(Action::Message { text: rest.trim().to_string() });
+        }
+        let fields: Vec<usize> = rest
+            .split_whitespace()
+            .map(|field| field.parse().map_err(|_| err()))
+            .collect::<Result<_, _>>()?;
+        // Checked, a cast would wrap 4294967297 around to 1
+        let amount = |n: usize| i32::try_from(n).ok().and_then(Amount::new).ok_or_else(err);
+        match (keyword, fields.as_slice()) {
+            ("MOVE", &[n, x1, y1, x2, y2]) =>
+                Ok(Action::Move { amount: amount(n)?, from: Pos::new(x1, y1), to: Pos::new(x2, y2) }),
+            ("BUILD", &[x, y]) =>
+                Ok(Action::Build { at: Pos::new(x, y) }),
+            ("SPAWN", &[n, x, y]) =>
+                Ok(Action::Spawn { amount: amount(n)?, at: Pos::new(x, y) }),
+            ("WAIT", &[]) =>
+                Ok(Action::Wait),
+            _ => Err(err()),
+        }
+    }
+}
+
+// A turn's output line, WAIT when there is nothing to do
+pub fn format_actions(actions: &[Action]) -> String {
+    if actions.is_empty() {
+        return Action::Wait.to_string();
+    }
+    actions.iter().map(|action| action.to_string()).collect::<Vec<String>>().join(";")
+}
+
+// A whole output line back into actions, the inverse of format_actions
+pub fn parse_actions(line: &str) -> Result<Vec<Action>, ParseActionError> {
+    line.split(';').filter(|part| !part.trim().is_empty()).map(str::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(n: i32) -> Amount {
+        Amount::new(n).unwrap()
+    }
+
+    #[test]
+    fn each_action_reads_back() {
+        let actions = [
+            Action::Move { amount: amount(3), from: Pos::new(1, 2), to: Pos::new(11, 5) },
+            Action::Build { at: Pos::new(0, 7) },
+            Action::Spawn { amount: amount(12), at: Pos::new(23, 11) },
+            Action::Wait,
+            Action::Message { text: "v1 a1b2c3d4".to_string() },
+        ];
+        for action in actions {
+            assert_eq!(action.to_string().parse::<Action>(), Ok(action.clone()), "{}", action);
+        }
+    }
+
+    #[test]
+    fn a_turn_line_reads_back() {
+        let line = "MOVE 2 3 4 4 4;BUILD 5 6;SPAWN 1 0 0;MESSAGE hold the line";
+        let actions = parse_actions(line).unwrap();
+        assert_eq!(actions.len(), 4);
+        assert_eq!(format_actions(&actions), line);
+        assert_eq!(parse_actions(&format_actions(&actions)), Ok(actions));
+    }
+
+    #[test]
+    fn nothing_to_do_is_wait() {
+        assert_eq!(format_actions(&[]), "WAIT");
+        assert_eq!(parse_actions("WAIT"), Ok(vec![Action::Wait]));
+        // The referee skips empty parts of a line
+        assert_eq!(parse_actions(" ; BUILD 1 1 ;"), Ok(vec![Action::Build { at: Pos::new(1, 1) }]));
+    }
+
+    #[test]
+    fn malformed_actions_are_rejected() {
+        for text in ["MOVE 0 1 1 2 1", "SPAWN 4294967297 1 1", "BUILD 1", "BUILD 1 2 3", "SPAWN -1 2 2", "JUMP 1 1", "WAIT 1", ""] {
+            assert!(text.parse::<Action>().is_err(), "{:?} parsed", text);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::cell::OnceCell;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::budget::UNIT_COST;
+use crate::game::{bfs, GameState, Owner, UNREACHABLE};
+use crate::grid::Grid;
+use crate::pos::Pos;
+
+// Whole-map analyses of one turn's board, each computed on first use and kept for the turn.
+// Voronoi and centrality only refine choices the planner can make without them: once the turn
+// has run longer than the budget they are not started any more, and their accessors give None.
+pub struct Analysis<'a> {
+    game: &'a GameState,
+    started: Instant,
+    budget_ms: Option<u128>,
+    dead_ends: OnceCell<Grid<bool>>,
+    threat: OnceCell<Grid<i32>>,
+    regions: OnceCell<Grid<Option<usize>>>,
+    voronoi: OnceCell<Grid<Owner>>,
+    centrality: OnceCell<Grid<i32>>,
+}
+
+impl<'a> Analysis<'a> {
+    pub fn new(game: &'a GameState) -> Self {
+        Analysis::with_budget(game, None)
+    }
+
+    // None never skips an analysis, so a seeded game plays the same however long it takes
+    pub fn with_budget(game: &'a GameState, budget_ms: Option<u128>) -> Self {
+        Analysis {
+            game,
+            started: Instant::now(),
+            budget_ms,
+            dead_ends: OnceCell::new(),
+            threat: OnceCell::new(),
+            regions: OnceCell::new(),
+            voronoi: OnceCell::new(),
+            centrality: OnceCell::new(),
+        }
+    }
+
+    // A skippable analysis already computed is given, one not started yet only within the budget
+    fn skippable<'s, T>(&'s self, cell: &'s OnceCell<T>, compute: impl FnOnce() -> T) -> Option<&'s T> {
+        let in_time = self.budget_ms.is_none_or(|budget_ms| self.started.elapsed().as_millis() < budget_ms);
+        if cell.get().is_none() && !in_time {
+            return None;
+        }
+        Some(cell.get_or_init(compute))
+    }
+
+    // Tiles of one-wide corridors leading nowhere, found by repeatedly peeling passable tiles
+    // with at most one passable neighbor
+    pub fn dead_ends(&self) -> &Grid<bool> {
+        self.dead_ends.get_or_init(|| {
+            let game = self.game;
+            let mut degree = Grid::new(game.width(), game.height(), 0);
+            let mut dead_ends = Grid::new(game.width(), game.height(), false);
+            let mut to_peel = Vec::new();
+            for pos in game.positions().filter(|&pos| game.passable(pos)) {
+                degree[pos] = game.neighbors(pos).filter(|&next| game.passable(next)).count();
+                if degree[pos] <= 1 {
+                    dead_ends[pos] = true;
+                    to_peel.push(pos);
+                }
+            }
+            while let Some(pos) = to_peel.pop() {
+                for next in game.neighbors(pos) {
+                    if !game.passable(next) || dead_ends[next] {
+                        continue;
+                    }
+                    degree[next] -= 1;
+                    if degree[next] <= 1 {
+                        dead_ends[next] = true;
+                        to_peel.push(next);
+                    }
+                }
+            }
+            dead_ends
+        })
+    }
+
+    // The board from my side, positive when I am ahead: the tiles each player owns, plus their
+    // units and matter counted as the tiles those can still take, a unit for every UNIT_COST
+    // matter. Nothing in it depends on where things are, so the mirrored board scores the same
+    // and the board seen by the enemy the opposite.
+    pub fn evaluation(&self) -> i32 {
+        let game = self.game;
+        let tiles = |owner: Owner| game.iter_owned(owner).count() as i32;
+        let units = game.count_units(Owner::Me) - game.count_units(Owner::Enemy);
+        tiles(Owner::Me) - tiles(Owner::Enemy) + units + (game.my_matter - game.enemy_matter) / UNIT_COST
+    }
+
+    // Enemy units standing on or next to each tile
+    pub fn threat(&self) -> &Grid<i32> {
+        self.threat.get_or_init(|| {
+            let game = self.game;
+            let mut threat = Grid::new(game.width(), game.height(), 0);
+            for (pos, loc) in game.iter_owned(Owner::Enemy).filter(|(_, loc)| loc.units > 0) {
+                threat[pos] += loc.units;
+                for next in game.neighbors(pos) {
+                    threat[next] += loc.units;
+                }
+            }
+            threat
+        })
+    }
+
+    // Connected areas of passable tiles, numbered in row-major order of their first tile
+    pub fn regions(&self) -> &Grid<Option<usize>> {
+        self.regions.get_or_init(|| {
+            let game = self.game;
+            let mut regions = Grid::new(game.width(), game.height(), None);
+            let mut n_regions = 0;
+            let mut stack = Vec::new();
+            for start in game.positions() {
+                if regions[start].is_some() || !game.passable(start) {
+                    continue;
+                }
+                regions[start] = Some(n_regions);
+                stack.push(start);
+                while let Some(pos) = stack.pop() {
+                    for next in game.neighbors(pos) {
+                        if regions[next].is_none() && game.passable(next) {
+                            regions[next] = Some(n_regions);
+                            stack.push(next);
+                        }
+                    }
+                }
+                n_regions += 1;
+            }
+            regions
+        })
+    }
+
+    // Which player's tiles are strictly closer to each tile, Neutral on ties and out of reach
+    pub fn voronoi(&self) -> Option<&Grid<Owner>> {
+        self.skippable(&self.voronoi, || {
+            let game = self.game;
+            let mut queue = VecDeque::new();
+            let mut dist = |owner: Owner| {
+                let sources: Vec<Pos> = game.iter_owned(owner).map(|(pos, _)| pos).collect();
+                let mut dist = Grid::new(game.width(), game.height(), UNREACHABLE);
+                bfs(&sources, &mut queue, &mut dist);
+                dist
+            };
+            let mine = dist(Owner::Me);
+            let theirs = dist(Owner::Enemy);
+            Grid::from_fn(game.width(), game.height(), |pos| {
+                if mine[pos] < theirs[pos] {
+                    Owner::Me
+                }
+                else if theirs[pos] < mine[pos] {
+                    Owner::Enemy
+                }
+                else {
+                    Owner::Neutral
+                }
+            })
+        })
+    }
+
+    // How central each passable tile is in its region: minus its total distance to the region's
+    // other tiles, so the higher the closer to everything. A search from every tile, the most
+    // expensive analysis here.
+    pub fn centrality(&self) -> Option<&Grid<i32>> {
+        self.skippable(&self.centrality, || {
+            let game = self.game;
+            let mut queue = VecDeque::new();
+            let mut dist = Grid::new(game.width(), game.height(), UNREACHABLE);
+            Grid::from_fn(game.width(), game.height(), |start| {
+                if !game.passable(start) {
+                    return 0;
+                }
+                dist.fill(UNREACHABLE);
+                dist[start] = 0;
+                queue.clear();
+                queue.push_back(start);
+                let mut total = 0;
+                while let Some(pos) = queue.pop_front() {
+                    total += dist[pos] as i32;
+                    for next in game.neighbors(pos) {
+                        if dist[next] == UNREACHABLE && game.passable(next) {
+                            dist[next] = dist[pos] + 1;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                -total
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two areas split by grass, the right one contested
+    const BOARD: &str = "
+        5m  5.  0   5m  5.  5e
+        5.  5.  0   5.  5.  5.
+    ";
+
+    #[test]
+    fn regions_are_split_by_grass() {
+        let game = GameState::from_ascii(BOARD).unwrap();
+        let regions = Analysis::new(&game).regions().clone();
+        assert_eq!(regions[Pos::new(0, 0)], Some(0));
+        assert_eq!(regions[Pos::new(1, 1)], Some(0));
+        assert_eq!(regions[Pos::new(2, 0)], None);
+        assert_eq!(regions[Pos::new(3, 0)], Some(1));
+        assert_eq!(regions[Pos::new(5, 1)], Some(1));
+    }
+
+    #[test]
+    fn voronoi_gives_ties_to_nobody() {
+        let game = GameState::from_ascii(BOARD).unwrap();
+        let analysis = Analysis::new(&game);
+        let voronoi = analysis.voronoi().unwrap();
+        assert_eq!(voronoi[Pos::new(3, 1)], Owner::Me);
+        assert_eq!(voronoi[Pos::new(4, 0)], Owner::Neutral);
+        assert_eq!(voronoi[Pos::new(4, 1)], Owner::Neutral);
+        assert_eq!(voronoi[Pos::new(5, 1)], Owner::Enemy);
+    }
+
+    #[test]
+    fn centrality_peaks_in_the_middle_of_a_region() {
+        let game = GameState::from_ascii(BOARD).unwrap();
+        let analysis = Analysis::new(&game);
+        let centrality = analysis.centrality().unwrap();
+        assert!(centrality[Pos::new(4, 0)] > centrality[Pos::new(5, 0)]);
+        assert!(centrality[Pos::new(4, 0)] > centrality[Pos::new(3, 1)]);
+    }
+
+    #[test]
+    fn skippable_analyses_are_skipped_past_the_budget() {
+        let game = GameState::from_ascii(BOARD).unwrap();
+        let analysis = Analysis::with_budget(&game, Some(0));
+        assert!(analysis.voronoi().is_none());
+        assert!(analysis.centrality().is_none());
+        assert_eq!(analysis.regions()[Pos::new(0, 0)], Some(0));
+        let unbounded = Analysis::new(&game);
+        assert!(unbounded.voronoi().is_some());
+    }
+}
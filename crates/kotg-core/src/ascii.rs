@@ -0,0 +1,115 @@
+// Board positions written as text, one row per line, one token per tile. A token is the scrap
+// amount, then the owner: `.` neutral, `m` me, `e` enemy, uppercase for a recycler, then the
+// units if any. Grass can be written `0` or `.`:
+//
+//   5m2 5m  5.  5e1
+//   .   5M  5.  5e
+//
+// The referee's flags are derived: can_spawn on my tiles without a recycler, can_build on those
+// also without units, in_range_of_recycler on and next to recyclers.
+use std::fmt;
+
+use crate::game::{derive_flags, GameConfig, GameState, Location, Owner};
+
+#[derive(Debug)]
+pub struct ParseMapError {
+    // Index among the map's rows
+    pub row: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "map row {}: {}", self.row, self.reason)
+    }
+}
+
+#[derive(Debug)]
+pub struct AsciiMap {
+    pub width: usize,
+    pub height: usize,
+    // Referee protocol fields per tile, row-major
+    pub tiles: Vec<[i32; 7]>,
+}
+
+// Scrap, owner, units and recycler of one token, the derived flags are filled in later
+fn parse_tile(token: &str) -> Option<[i32; 7]> {
+    if token == "." {
+        return Some([0, Owner::Neutral.to_code(), 0, 0, 0, 0, 0]);
+    }
+    let split = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+    let (scrap, rest) = token.split_at(split);
+    let scrap: i32 = scrap.parse().ok()?;
+    let mut chars = rest.chars();
+    let (owner, recycler) = match chars.next() {
+        None if scrap == 0 => (Owner::Neutral, 0),
+        Some('.') => (Owner::Neutral, 0),
+        Some('m') => (Owner::Me, 0),
+        Some('e') => (Owner::Enemy, 0),
+        Some('M') => (Owner::Me, 1),
+        Some('E') => (Owner::Enemy, 1),
+        _ => return None,
+    };
+    let units_str = chars.as_str();
+    let units: i32 = if units_str.is_empty() { 0 } else { units_str.parse().ok()? };
+    Some([scrap, owner.to_code(), units, recycler, 0, 0, 0])
+}
+
+// The tile's token, grass as `.`
+pub fn format_tile(loc: &Location) -> String {
+    if loc.scrap_amount == 0 {
+        return ".".to_string();
+    }
+    let owner = match (loc.owner, loc.recycler) {
+        (Owner::Neutral, _) => '.',
+        (Owner::Me, false) => 'm',
+        (Owner::Enemy, false) => 'e',
+        (Owner::Me, true) => 'M',
+        (Owner::Enemy, true) => 'E',
+    };
+    let units = if loc.units > 0 { loc.units.to_string() } else { String::new() };
+    format!("{}{}{}", loc.scrap_amount, owner, units)
+}
+
+// The whole board on one line, matter first and rows split by ` / `: `10 10 / 5m2 5. / . 5e`
+pub fn format_board(game: &GameState) -> String {
+    let rows: Vec<String> = game.grid.rows().map(|row| row.iter().map(format_tile).collect::<Vec<String>>().join(" ")).collect();
+    format!("{} {} / {}", game.my_matter, game.enemy_matter, rows.join(" / "))
+}
+
+pub fn parse_map<'a>(rows: impl IntoIterator<Item = &'a str>) -> Result<AsciiMap, ParseMapError> {
+    let mut width = 0;
+    let mut height = 0;
+    let mut tiles: Vec<[i32; 7]> = Vec::new();
+    for (row, line) in rows.into_iter().enumerate() {
+        let err = |reason: String| ParseMapError { row, reason };
+        let before = tiles.len();
+        for token in line.split_whitespace() {
+            tiles.push(parse_tile(token).ok_or_else(|| err(format!("bad tile {:?}", token)))?);
+        }
+        let row_width = tiles.len() - before;
+        if row == 0 {
+            width = row_width;
+        }
+        if row_width == 0 || row_width != width {
+            return Err(err(format!("row of {} tiles, expected {}", row_width, width.max(1))));
+        }
+        height += 1;
+    }
+    if height == 0 {
+        return Err(ParseMapError { row: 0, reason: "no rows".to_string() });
+    }
+    derive_flags(&mut tiles, width);
+    Ok(AsciiMap { width, height, tiles })
+}
+
+impl GameState {
+    // The first turn on the drawn board, with no matter on either side
+    pub fn from_ascii(map: &str) -> Result<GameState, ParseMapError> {
+        let map = parse_map(map.lines().map(str::trim).filter(|line| !line.is_empty()))?;
+        let mut game = GameState::new(&GameConfig { width: map.width, height: map.height });
+        let result = game.update(0, 0, &map.tiles);
+        debug_invariant!(result.is_ok(), "map tiles rejected: {:?}", result);
+        Ok(game)
+    }
+}
@@ -0,0 +1,95 @@
+// Replays downloaded from CodinGame turned into replays written the way the arena writes them, so
+// ladder games go through the same tools as local ones:
+//
+//   cargo run -q -p kotg-core --bin convert -- [--out DIR] [--golden] <files>
+//
+// Each file becomes DIR/<name>.json, replays/ by default, with every turn's board, input and
+// lines, played again with the local referee from the BOARD line the bot writes on its first turn.
+// So only games where one of the agents is this bot convert: CodinGame's frames hold what each
+// agent wrote but not the board, and the viewer's own frames are not read here. A game whose
+// first stderr CodinGame cut before the BOARD line does not convert either.
+// With --golden the bot's side is also written as golden/<name>.in and golden/<name>.played, and
+// `golden --bless` then writes what the bot plays on it.
+// A file that does not convert is reported and the others go on, the run fails at the end.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use kotg_core::json::Json;
+use kotg_core::replay::Replay;
+
+const DEFAULT_OUT_DIR: &str = "replays";
+const GOLDEN_DIR: &str = "golden";
+
+// The bot's inputs one after the other, and both lines of each turn, the bot's first
+fn golden_files(converted: &Json, bot: usize) -> Result<(String, String), String> {
+    let turns = Replay::recorded_turns(converted, bot)?;
+    let opponent_turns = Replay::recorded_turns(converted, 1 - bot)?;
+    let input = turns.iter().map(|turn| turn.input.as_str()).collect();
+    let played = turns.iter().zip(&opponent_turns).map(|(turn, opponent)| format!("{}\n{}\n", turn.stdout.trim_end(), opponent.stdout.trim_end())).collect();
+    Ok((input, played))
+}
+
+fn convert(path: &Path, out_dir: &Path, golden: bool) -> Result<String, String> {
+    let json: Json = fs::read_to_string(path).map_err(|err| format!("cannot read it: {}", err))?.parse().map_err(|err| format!("{}", err))?;
+    let replay = Replay::from_codingame(&json)?;
+    let converted = replay.to_json();
+    let name = path.file_stem().map_or_else(|| "game".into(), |stem| stem.to_string_lossy());
+    let out_path = out_dir.join(format!("{}.json", name));
+    fs::write(&out_path, converted.to_string()).map_err(|err| format!("cannot write {}: {}", out_path.display(), err))?;
+    let n_turns = Replay::recorded_turns(&converted, 0)?.len();
+    let mut written = format!("{}, {} turns", out_path.display(), n_turns);
+    if golden {
+        let bot = Replay::logged_agent(&json).ok_or("neither agent is this bot")?;
+        let (input, played) = golden_files(&converted, bot)?;
+        let base = Path::new(GOLDEN_DIR).join(name.as_ref());
+        for (extension, text) in [("in", input), ("played", played)] {
+            let golden_path = base.with_extension(extension);
+            fs::write(&golden_path, text).map_err(|err| format!("cannot write {}: {}", golden_path.display(), err))?;
+        }
+        written.push_str(&format!(" and {}.in, the bot as player {}", base.display(), bot));
+    }
+    Ok(written)
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let golden = match args.iter().position(|arg| arg == "--golden") {
+        Some(k) => {
+            args.remove(k);
+            true
+        }
+        None => false,
+    };
+    let out_dir = match args.iter().position(|arg| arg == "--out") {
+        Some(k) if k + 1 < args.len() => {
+            args.remove(k);
+            PathBuf::from(args.remove(k))
+        }
+        Some(_) => {
+            eprintln!("convert: --out needs a directory");
+            return ExitCode::FAILURE;
+        }
+        None => PathBuf::from(DEFAULT_OUT_DIR),
+    };
+    if args.is_empty() || args.iter().any(|arg| arg.starts_with("--")) {
+        eprintln!("convert: usage: convert [--out DIR] [--golden] <files>");
+        return ExitCode::FAILURE;
+    }
+    if let Err(err) = fs::create_dir_all(&out_dir) {
+        eprintln!("convert: cannot create {}: {}", out_dir.display(), err);
+        return ExitCode::FAILURE;
+    }
+    let mut n_failed = 0;
+    for path in &args {
+        match convert(Path::new(path), &out_dir, golden) {
+            Ok(written) => println!("ok    {} -> {}", path, written),
+            Err(err) => {
+                n_failed += 1;
+                println!("FAIL  {}: {}", path, err);
+            }
+        }
+    }
+    println!("{} files, {} converted, {} failed", args.len(), args.len() - n_failed, n_failed);
+    if n_failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
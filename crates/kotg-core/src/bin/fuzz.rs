@@ -0,0 +1,219 @@
+// Malformed input against the code that reads it, which must answer with errors and never panic:
+// a panic on the ladder loses the game.
+//
+//   cargo run -q -p kotg-core --bin fuzz -- [runs] [first seed]
+//
+// The same two targets run under libFuzzer with cargo-fuzz, on nightly, from crates/kotg-core:
+//
+//   cargo +nightly fuzz run frames
+//   cargo +nightly fuzz run actions
+//
+// This one runs on the stable toolchain, so `cargo test` plays it on every build.
+// Two targets, each run gets one input for both:
+// - frames: the referee's input for a generated game, mutated by flipping, inserting, dropping
+//   and repeating bytes and lines and by swapping numbers for extreme ones, or plain random bytes,
+//   read with io::read_game and io::read_turn until it runs out
+// - actions: output lines built from actions, keywords and numbers picked to be wrong, played
+//   through Referee::step, each action that parses holding the numbers written
+// Debug builds stop on a broken invariant on purpose (see debug_invariant), a garbled frame may
+// trip one and that is not counted; any other panic is. Debug builds also catch overflows that
+// the optimized build on CodinGame would wrap, so run both.
+// A panicking input is shrunk line by line, then printed with its seed. A failed allocation
+// aborts instead of panicking and ends the whole run, `fuzz 1 <seed>` then finds its seed.
+use std::io::{BufRead, Cursor};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+use kotg_core::io::{self, InputStatus};
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::referee::Referee;
+use kotg_core::{Action, GameState, Pos, Rng};
+
+const DEFAULT_RUNS: u64 = 20000;
+const INVARIANT_PANIC: &str = "invariant violated";
+const EXTREME_NUMBERS: [&str; 11] = ["-1", "0", "-0", "2147483647", "-2147483648", "2147483648", "4294967296", "4294967297", "99999999999999999999", "1e3", "0x10"];
+const KEYWORDS: [&str; 6] = ["MOVE", "SPAWN", "BUILD", "WAIT", "MESSAGE", "move"];
+
+struct Target<'a> {
+    name: &'static str,
+    run: &'a dyn Fn(&[u8]),
+    // Whether a broken invariant is an acceptable answer to the input
+    invariants_allowed: bool,
+}
+
+fn pick<'a>(rng: &mut Rng, items: &[&'a str]) -> &'a str {
+    items[rng.gen_range(0..items.len() as i32) as usize]
+}
+
+fn frames_target(input: &[u8]) {
+    let mut input = Cursor::new(input);
+    let Ok(config) = io::read_game(&mut input) else { return };
+    let mut game = GameState::new(&config);
+    while !matches!(io::read_turn(&mut input, &mut game), InputStatus::Closed) {}
+}
+
+// An action that parses holds the numbers written in the line, not ones that wrapped around
+fn check_numbers(line: &str) {
+    let numbers = |text: &str| -> Vec<i128> { text.split_whitespace().skip(1).filter_map(|field| field.parse().ok()).collect() };
+    for part in line.split(';') {
+        match part.parse::<Action>() {
+            Ok(Action::Message { .. }) | Err(_) => (),
+            Ok(action) => assert_eq!(numbers(part), numbers(&action.to_string()), "{:?} read as {}", part, action),
+        }
+    }
+}
+
+fn actions_target(start: &GameState, lines: &[u8]) {
+    let text = String::from_utf8_lossy(lines);
+    let mut outputs = text.lines();
+    let mut referee = Referee::new(start);
+    while let (Some(first), Some(second)) = (outputs.next(), outputs.next()) {
+        check_numbers(first);
+        check_numbers(second);
+        referee.step([first, second]);
+    }
+}
+
+// A few turns of a real game, so mutations start from input the referee could send
+fn real_frames(seed: u64) -> Vec<u8> {
+    let mut referee = Referee::new(&MapGenerator::generate(seed));
+    let mut text = String::new();
+    for _ in 0..3 {
+        text.push_str(&referee.input(0));
+        referee.step(["WAIT", "WAIT"]);
+    }
+    text.into_bytes()
+}
+
+fn mutate(rng: &mut Rng, mut bytes: Vec<u8>) -> Vec<u8> {
+    if rng.gen_range(0..10) == 0 {
+        return (0..rng.gen_range(0..200)).map(|_| rng.gen_range(0..256) as u8).collect();
+    }
+    for _ in 0..rng.gen_range(1..6) {
+        let at = rng.gen_range(0..bytes.len() as i32 + 1) as usize;
+        match rng.gen_range(0..7) {
+            0 if at < bytes.len() => bytes[at] = rng.gen_range(0..256) as u8,
+            1 => bytes.insert(at, pick(rng, &[" ", "\n", "-", "7", "\r", "\t"]).as_bytes()[0]),
+            2 => bytes.truncate(at),
+            3 => {
+                let end = (at + rng.gen_range(1..40) as usize).min(bytes.len());
+                bytes.drain(at..end);
+            }
+            4 | 5 => {
+                let mut lines: Vec<&[u8]> = bytes.split(|&b| b == b'\n').collect();
+                let k = rng.gen_range(0..lines.len() as i32) as usize;
+                let extreme = pick(rng, &EXTREME_NUMBERS).as_bytes();
+                let line: Vec<u8> = if rng.gen_range(0..2) == 0 {
+                    lines[k].to_vec()
+                } else {
+                    let mut fields: Vec<&[u8]> = lines[k].split(|&b| b == b' ').collect();
+                    let f = rng.gen_range(0..fields.len() as i32) as usize;
+                    fields[f] = extreme;
+                    fields.join(&b' ')
+                };
+                lines.insert(k, &line);
+                let joined = lines.join(&b'\n');
+                bytes = joined;
+            }
+            _ => {
+                let k = rng.gen_range(0..bytes.len() as i32 + 1) as usize;
+                let extreme = pick(rng, &EXTREME_NUMBERS);
+                bytes.splice(k..k, format!(" {} ", extreme).into_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+// Half the actions are aimed at a tile with units on it, so they get past the tile checks and
+// their amounts are what gets tested
+fn random_output(rng: &mut Rng, start: &GameState) -> String {
+    let number = |rng: &mut Rng| if rng.gen_range(0..3) == 0 { pick(rng, &EXTREME_NUMBERS).to_string() } else { rng.gen_range(0..30).to_string() };
+    let occupied: Vec<Pos> = start.positions().filter(|&pos| start.loc(pos).units > 0).collect();
+    let actions: Vec<String> = (0..rng.gen_range(0..6))
+        .map(|_| {
+            let keyword = pick(rng, &KEYWORDS);
+            let fields: Vec<String> = if rng.gen_range(0..2) == 0 {
+                let at = occupied[rng.gen_range(0..occupied.len() as i32) as usize];
+                let amount = number(rng);
+                let (x, y) = (at.x().to_string(), (at.y() as i32 + rng.gen_range(-1..2)).to_string());
+                vec![amount, x, y, number(rng), number(rng)].into_iter().take(rng.gen_range(1..6) as usize).collect()
+            } else {
+                (0..rng.gen_range(0..7)).map(|_| number(rng)).collect()
+            };
+            format!("{} {}", keyword, fields.join(pick(rng, &[" ", "  ", "\t"])))
+        })
+        .collect();
+    actions.join(pick(rng, &[";", "; ", ";;", " ;"]))
+}
+
+// The panic's message, None if the target returned or only tripped an invariant on a bad frame
+fn panics(target: &Target, input: &[u8], last_panic: &Mutex<String>) -> Option<String> {
+    if panic::catch_unwind(AssertUnwindSafe(|| (target.run)(input))).is_ok() {
+        return None;
+    }
+    let message = last_panic.lock().map(|message| message.clone()).unwrap_or_default();
+    if target.invariants_allowed && message.contains(INVARIANT_PANIC) { None } else { Some(message) }
+}
+
+// Drops lines for as long as the input keeps panicking
+fn shrink(target: &Target, mut input: Vec<u8>, last_panic: &Mutex<String>) -> Vec<u8> {
+    loop {
+        let lines: Vec<&[u8]> = input.split(|&b| b == b'\n').collect();
+        let smaller = (0..lines.len()).find_map(|k| {
+            let candidate: Vec<u8> = lines.iter().enumerate().filter(|&(j, _)| j != k).map(|(_, line)| *line).collect::<Vec<_>>().join(&b'\n');
+            panics(target, &candidate, last_panic).map(|_| candidate)
+        });
+        match smaller {
+            Some(candidate) => input = candidate,
+            None => return input,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parse = |k: usize, default: u64| args.get(k).map_or(Ok(default), |arg| arg.parse::<u64>().map_err(|_| arg.clone()));
+    let (n_runs, first_seed) = match (parse(0, DEFAULT_RUNS), parse(1, 0)) {
+        (Ok(n_runs), Ok(first_seed)) => (n_runs, first_seed),
+        (Err(arg), _) | (_, Err(arg)) => {
+            eprintln!("fuzz: {:?} is not a number, usage: fuzz [runs] [first seed]", arg);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Panics are expected here, keep their message instead of printing it
+    let last_panic = Arc::new(Mutex::new(String::new()));
+    let hook_panic = Arc::clone(&last_panic);
+    panic::set_hook(Box::new(move |info| {
+        if let Ok(mut message) = hook_panic.lock() {
+            *message = info.to_string();
+        }
+    }));
+    let starts: Vec<GameState> = (0..8).map(MapGenerator::generate).collect();
+    let bases: Vec<Vec<u8>> = (0..8).map(real_frames).collect();
+    for seed in first_seed..first_seed + n_runs {
+        let mut rng = Rng::new(seed);
+        let frames = mutate(&mut rng, bases[seed as usize % bases.len()].clone());
+        let start = &starts[seed as usize % starts.len()];
+        let outputs = (0..2 * rng.gen_range(1..4)).map(|_| random_output(&mut rng, start)).collect::<Vec<String>>().join("\n").into_bytes();
+        let play_actions = |lines: &[u8]| actions_target(start, lines);
+        let targets = [
+            (Target { name: "frames", run: &frames_target, invariants_allowed: true }, frames),
+            (Target { name: "actions", run: &play_actions, invariants_allowed: false }, outputs),
+        ];
+        for (target, input) in targets {
+            if panics(&target, &input, &last_panic).is_some() {
+                let input = shrink(&target, input, &last_panic);
+                let message = panics(&target, &input, &last_panic).unwrap_or_default();
+                println!("PANIC {} seed {}: {}", target.name, seed, message);
+                for line in input.lines() {
+                    println!("  {:?}", line.unwrap_or_default());
+                }
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    println!("{} runs, no panic", n_runs);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,198 @@
+// Whole recorded games replayed through the bot, to catch a refactor that changes what it plays:
+//
+//   cargo run -q -p kotg-core --bin golden -- [--bless] [files]
+//
+// Each golden/<name>.in holds the frames of one game exactly as the bot reads them on stdin, and
+// golden/<name>.out the line the bot plays each turn, messages left out. The bot's random choices
+// are seeded, so the lines only change when the bot does, and `kotg-bot --seed 0 < <name>.in`
+// plays them too. When the change is meant, --bless writes the .out files again from what the bot
+// now plays, and the diff goes in with it.
+// A golden/<name>.played next to a game holds both lines the referee was given each turn, the
+// bot's then its opponent's: playing both lines from a frame must give the next one tile for
+// tile, matter included. The games were recorded by the arena, whose referee plays turns with
+// this same simulator, so this is a regression check: it catches a change to the simulator, not
+// a rule the simulator always had wrong. Checking the rules takes frames from CodinGame or from
+// the official referee, which none of these are. Then the opponent's line is replaced by the one
+// inference rebuilds from the two frames, which must give the same frame on turns where no unit
+// died; units lost in fights leave no trace to rebuild their moves from, those turns are only
+// counted.
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use kotg_core::budget::{RECYCLER_COST, UNIT_COST};
+use kotg_core::inference::infer_enemy_actions;
+use kotg_core::io::{self, InputStatus};
+use kotg_core::referee::{Bot, StrategyBot};
+use kotg_core::scenario::split_turns;
+use kotg_core::{format_actions, parse_actions, Action, Config, GameState, Location, Owner};
+
+const GOLDEN_DIR: &str = "golden";
+const BOT_SEED: u64 = 0;
+// Differences printed for a failing game, the rest are only counted
+const SHOWN_DIFFERENCES: usize = 5;
+
+// The bot's line without its messages, which carry the build time
+fn without_messages(line: &str) -> String {
+    match parse_actions(line) {
+        Ok(actions) => format_actions(&actions.into_iter().filter(|action| !matches!(action, Action::Message { .. })).collect::<Vec<_>>()),
+        Err(_) => line.trim().to_string(),
+    }
+}
+
+fn play(input_path: &Path) -> Result<Vec<String>, String> {
+    let input = fs::read_to_string(input_path).map_err(|err| err.to_string())?;
+    let mut bot = StrategyBot::new(Config { seed: Some(BOT_SEED), ..Config::default() });
+    Ok(split_turns(&input)?.iter().map(|turn| without_messages(&bot.play(turn))).collect())
+}
+
+// The board of each turn as the bot read it
+fn boards(turns: &[String]) -> Result<Vec<GameState>, String> {
+    let mut game: Option<GameState> = None;
+    let mut boards = Vec::new();
+    for (k, turn) in turns.iter().enumerate() {
+        let mut input = Cursor::new(turn.as_str());
+        let game = match &mut game {
+            Some(game) => game,
+            None => game.insert(GameState::new(&io::read_game(&mut input).map_err(|err| format!("{:?}", err))?)),
+        };
+        if !matches!(io::read_turn(&mut input, game), InputStatus::Ready) {
+            return Err(format!("turn {} cannot be read", k + 1));
+        }
+        boards.push(game.clone());
+    }
+    Ok(boards)
+}
+
+// The first thing the simulated frame gets wrong, None if it is the recorded one
+fn frame_difference(simulated: &GameState, recorded: &GameState) -> Option<String> {
+    if (simulated.my_matter, simulated.enemy_matter) != (recorded.my_matter, recorded.enemy_matter) {
+        return Some(format!("matter {} {} instead of {} {}", simulated.my_matter, simulated.enemy_matter, recorded.my_matter, recorded.enemy_matter));
+    }
+    recorded.positions().find_map(|pos| {
+        let (got, want) = (simulated.loc(pos), recorded.loc(pos));
+        let tile = |loc: &Location| (loc.scrap_amount, loc.owner, loc.units, loc.recycler);
+        (tile(got) != tile(want)).then(|| format!("{:?} is {:?} instead of {:?}", pos, tile(got), tile(want)))
+    })
+}
+
+// The enemy units that died during the turn, from the matter they spent on units
+fn enemy_losses(before: &GameState, after: &GameState) -> i32 {
+    let mut with_builds = before.clone();
+    let mut n_builds = 0;
+    for pos in before.positions().filter(|&pos| before.loc(pos).owner == Owner::Enemy && !before.loc(pos).recycler && after.loc(pos).recycler) {
+        with_builds.grid[pos].recycler = true;
+        n_builds += 1;
+    }
+    let spent = before.enemy_matter + with_builds.income(Owner::Enemy) - after.enemy_matter;
+    let spawned = (spent - RECYCLER_COST * n_builds) / UNIT_COST;
+    before.count_units(Owner::Enemy) + spawned - after.count_units(Owner::Enemy)
+}
+
+// The turns the simulator now plays differently from when they were recorded, and the number of
+// turns inference was checked on
+fn check_frames(input_path: &Path, played_path: &Path) -> Result<(Vec<String>, usize), String> {
+    let boards = boards(&split_turns(&fs::read_to_string(input_path).map_err(|err| err.to_string())?)?)?;
+    let played = fs::read_to_string(played_path).map_err(|err| err.to_string())?;
+    let lines: Vec<&str> = played.lines().collect();
+    if lines.len() != 2 * boards.len() {
+        return Err(format!("{} lines for {} turns, two a turn expected", lines.len(), boards.len()));
+    }
+    let mut differences = Vec::new();
+    let mut n_inferred = 0;
+    for (k, frames) in boards.windows(2).enumerate() {
+        let [before, after] = frames else { unreachable!() };
+        let parse = |line: &str| parse_actions(line).map_err(|err| format!("turn {}: {:?}", k + 1, err));
+        let (mine, theirs) = (parse(lines[2 * k])?, parse(lines[2 * k + 1])?);
+        if let Some(difference) = frame_difference(&before.simulate(&mine, &theirs), after) {
+            differences.push(format!("  turn {}, both lines recorded, the simulator changed: {}", k + 1, difference));
+        } else if enemy_losses(before, after) == 0 {
+            n_inferred += 1;
+            let inferred = infer_enemy_actions(before, after);
+            if let Some(difference) = frame_difference(&before.simulate(&mine, &inferred), after) {
+                differences.push(format!("  turn {}, enemy line inferred as {}: {}", k + 1, format_actions(&inferred), difference));
+            }
+        }
+    }
+    Ok((differences, n_inferred))
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let bless = args.iter().any(|arg| arg == "--bless");
+    args.retain(|arg| arg != "--bless");
+    let mut paths: Vec<PathBuf> = args.into_iter().map(PathBuf::from).collect();
+    if paths.is_empty() {
+        match fs::read_dir(GOLDEN_DIR) {
+            Ok(entries) => paths = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| path.extension().is_some_and(|ext| ext == "in")).collect(),
+            Err(err) => {
+                eprintln!("cannot list {}: {}", GOLDEN_DIR, err);
+                return ExitCode::FAILURE;
+            }
+        }
+        paths.sort();
+    }
+    let mut n_failed = 0;
+    for path in paths.iter() {
+        let expected_path = path.with_extension("out");
+        let played = match play(path) {
+            Ok(played) => played,
+            Err(err) => {
+                println!("ERROR {}: {}", path.display(), err);
+                n_failed += 1;
+                continue;
+            }
+        };
+        if bless {
+            let text: String = played.iter().map(|line| format!("{}\n", line)).collect();
+            match fs::write(&expected_path, text) {
+                Ok(()) => println!("blessed {}, {} turns", expected_path.display(), played.len()),
+                Err(err) => {
+                    println!("ERROR {}: {}", expected_path.display(), err);
+                    n_failed += 1;
+                }
+            }
+            continue;
+        }
+        let expected = match fs::read_to_string(&expected_path) {
+            Ok(text) => text.lines().map(str::to_string).collect::<Vec<String>>(),
+            Err(err) => {
+                println!("ERROR {}: {}, --bless writes it", expected_path.display(), err);
+                n_failed += 1;
+                continue;
+            }
+        };
+        let mut differences: Vec<String> = played
+            .iter()
+            .zip(expected.iter())
+            .enumerate()
+            .filter(|(_, (played, expected))| played != expected)
+            .map(|(k, (played, expected))| format!("  turn {}\n    expected {}\n    played   {}", k + 1, expected, played))
+            .collect();
+        if played.len() != expected.len() {
+            differences.push(format!("  {} turns played, {} expected", played.len(), expected.len()));
+        }
+        let played_path = path.with_extension("played");
+        if played_path.exists() {
+            match check_frames(path, &played_path) {
+                Ok((frame_differences, n_inferred)) => {
+                    println!("      {}: simulator unchanged on the recorded frames, {} turns checked with the enemy line inferred", played_path.display(), n_inferred);
+                    differences.extend(frame_differences);
+                }
+                Err(err) => differences.push(format!("  {}: {}", played_path.display(), err)),
+            }
+        }
+        if differences.is_empty() {
+            println!("ok    {}", path.display());
+        } else {
+            println!("FAIL  {}: {} differences", path.display(), differences.len());
+            for difference in differences.iter().take(SHOWN_DIFFERENCES) {
+                println!("{}", difference);
+            }
+            n_failed += 1;
+        }
+    }
+    println!("{} games, {} failed", paths.len(), n_failed);
+    if n_failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
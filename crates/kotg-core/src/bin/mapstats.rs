@@ -0,0 +1,118 @@
+// The generated maps' statistics next to those of ladder maps, to check the generator against the
+// contest's rather than against what it was rebuilt from:
+//
+//   cargo run -q --release -p kotg-core --bin mapstats -- [--maps N] <replay files>
+//
+// The files are replays downloaded from CodinGame where one of the agents is this bot, whose
+// BOARD line gives the first board, or replays converted from them. The first N generated maps,
+// 200 by default, are measured the same way: size, grass, scrap, how far apart the starts are,
+// whether the board is symmetric through its center, and the share of each archetype. Without
+// files only the generated column is printed.
+use std::fs;
+use std::process::ExitCode;
+
+use kotg_core::json::Json;
+use kotg_core::mapgen::{archetype, MapGenerator, ARCHETYPES, MAX_SCRAP};
+use kotg_core::replay::Replay;
+use kotg_core::{GameState, Owner};
+
+const DEFAULT_MAPS: u64 = 200;
+
+#[derive(Default)]
+struct Stats {
+    n_boards: usize,
+    width_total: usize,
+    grass_share_total: f64,
+    // Tiles by scrap amount, grass first
+    scrap_counts: [usize; MAX_SCRAP as usize + 1],
+    start_distance_total: f64,
+    n_symmetric: usize,
+    archetype_counts: [usize; ARCHETYPES.len()],
+}
+
+// The mean column and row of the owner's tiles
+fn center(game: &GameState, owner: Owner) -> (f64, f64) {
+    let tiles: Vec<(f64, f64)> = game.iter_owned(owner).map(|(pos, _)| (pos.x() as f64, pos.y() as f64)).collect();
+    let n = tiles.len().max(1) as f64;
+    (tiles.iter().map(|tile| tile.0).sum::<f64>() / n, tiles.iter().map(|tile| tile.1).sum::<f64>() / n)
+}
+
+impl Stats {
+    fn add(&mut self, game: &GameState) {
+        let (width, height) = (game.width(), game.height());
+        let n_tiles = (width * height) as f64;
+        self.n_boards += 1;
+        self.width_total += width;
+        for pos in game.positions() {
+            self.scrap_counts[game.loc(pos).scrap_amount.clamp(0, MAX_SCRAP) as usize] += 1;
+        }
+        self.grass_share_total += game.positions().filter(|&pos| game.loc(pos).scrap_amount == 0).count() as f64 / n_tiles;
+        let (mine, theirs) = (center(game, Owner::Me), center(game, Owner::Enemy));
+        self.start_distance_total += (mine.0 - theirs.0).abs() + (mine.1 - theirs.1).abs();
+        self.n_symmetric += game.positions().all(|pos| game.loc(pos).scrap_amount == game.loc(pos.mirrored(width, height)).scrap_amount) as usize;
+        self.archetype_counts[ARCHETYPES.iter().position(|&known| known == archetype(game)).unwrap_or(0)] += 1;
+    }
+
+    // The rows printed, a label and a value
+    fn rows(&self) -> Vec<(String, String)> {
+        let n = self.n_boards.max(1) as f64;
+        let n_tiles: usize = self.scrap_counts.iter().sum();
+        let share = |count: usize, of: usize| format!("{:.1}%", 100.0 * count as f64 / of.max(1) as f64);
+        let mut rows = vec![
+            ("boards".to_string(), self.n_boards.to_string()),
+            ("mean width".to_string(), format!("{:.1}", self.width_total as f64 / n)),
+            ("mean grass share".to_string(), format!("{:.1}%", 100.0 * self.grass_share_total / n)),
+            ("mean start distance".to_string(), format!("{:.1}", self.start_distance_total / n)),
+            ("symmetric".to_string(), share(self.n_symmetric, self.n_boards)),
+        ];
+        rows.extend(self.scrap_counts.iter().enumerate().map(|(scrap, &count)| (format!("tiles with scrap {}", scrap), share(count, n_tiles))));
+        rows.extend(ARCHETYPES.iter().zip(self.archetype_counts).map(|(name, count)| (name.to_string(), share(count, self.n_boards))));
+        rows
+    }
+}
+
+// The first board of the game in the file
+fn first_board(path: &str) -> Result<GameState, String> {
+    let json: Json = fs::read_to_string(path).map_err(|err| format!("cannot read it: {}", err))?.parse().map_err(|err| format!("{}", err))?;
+    match Replay::recorded_board(&json, 1) {
+        Ok(board) => Ok(board),
+        Err(_) => Replay::recorded_board(&Replay::from_codingame(&json)?.to_json(), 1),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let n_maps = match args.iter().position(|arg| arg == "--maps") {
+        Some(k) => {
+            args.remove(k);
+            match (k < args.len()).then(|| args.remove(k)).and_then(|arg| arg.parse::<u64>().ok()) {
+                Some(n_maps) => n_maps,
+                None => {
+                    eprintln!("mapstats: --maps needs a number, usage: mapstats [--maps N] <replay files>");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        None => DEFAULT_MAPS,
+    };
+    let mut generated = Stats::default();
+    for seed in 0..n_maps {
+        generated.add(&MapGenerator::generate(seed));
+    }
+    let mut ladder = Stats::default();
+    let mut n_failed = 0;
+    for path in &args {
+        match first_board(path) {
+            Ok(board) => ladder.add(&board),
+            Err(err) => {
+                n_failed += 1;
+                eprintln!("mapstats: {}: {}", path, err);
+            }
+        }
+    }
+    println!("{:<22}{:>12}{:>12}", "", "generated", if args.is_empty() { "" } else { "ladder" });
+    for ((label, mine), (_, theirs)) in generated.rows().into_iter().zip(ladder.rows()) {
+        println!("{:<22}{:>12}{:>12}", label, mine, if args.is_empty() { String::new() } else { theirs });
+    }
+    if n_failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,109 @@
+// A failing scenario shrunk to the smallest position that still fails the same way, to debug on
+// a few tiles rather than a whole board:
+//
+//   cargo run -q --release -p kotg-core --bin minimize -- <scenario file>
+//   cargo run -q --release -p kotg-core --bin minimize -- --golden golden/<name>.in <turn>
+//
+// A scenario fails the same way when the same expectations break, or for one checking the
+// simulator, when the same kinds of mismatch show. The earlier maps are dropped from the first
+// while that holds, then rows and columns are cropped from each side, then tiles are turned to
+// grass one by one, and the whole is tried again until nothing more goes. Tiles an expectation or
+// a played action names stay. Golden games and simcheck's random turns print their failures as
+// positions, so this is the next step after either.
+// With --golden the turn of a golden game whose line differs becomes a scenario first: every turn
+// before it as earlier maps, and the line golden/<name>.out holds for it as exact expectations,
+// one per tile the line acts on and one per action kind for the totals.
+// The smallest scenario is printed in the scenario format, ready to go into scenarios/ once the
+// expectations say what should happen rather than what used to.
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use kotg_core::scenario::{split_turns, Scenario};
+
+// The scenario file, or the scenario of a golden game's turn
+fn load(args: &[String]) -> Result<(String, Scenario), String> {
+    match args {
+        [path] => {
+            let text = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+            Ok((path.clone(), text.parse().map_err(|err| format!("{}: {}", path, err))?))
+        }
+        [flag, path, turn] if flag == "--golden" => {
+            let turn: usize = turn.parse().map_err(|_| format!("turn {:?} is not a number", turn))?;
+            let input = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+            let out_path = Path::new(path).with_extension("out");
+            let out = fs::read_to_string(&out_path).map_err(|err| format!("cannot read {}: {}", out_path.display(), err))?;
+            let expected = out.lines().nth(turn.max(1) - 1).ok_or_else(|| format!("{} has no line for turn {}", out_path.display(), turn))?;
+            Ok((format!("{} turn {}", path, turn), Scenario::from_golden(&split_turns(&input)?, turn, expected)?))
+        }
+        _ => Err("usage: minimize <scenario file> | minimize --golden <name>.in <turn>".to_string()),
+    }
+}
+
+// Shrinks while the failures stay those given, and counts the scenarios tried
+fn minimize(mut scenario: Scenario, failures: &[String]) -> (Scenario, usize) {
+    let mut n_tried = 0;
+    let mut fails = |candidate: &Scenario| {
+        n_tried += 1;
+        candidate.failures() == failures
+    };
+    loop {
+        let mut shrunk = false;
+        while let Some(shorter) = scenario.without_earliest().filter(&mut fails) {
+            scenario = shorter;
+            shrunk = true;
+        }
+        // Left, right, top and bottom
+        for side in 0..4 {
+            loop {
+                let (width, height) = (scenario.width, scenario.height);
+                let rect = match side {
+                    0 => (1, 0, width - 1, height),
+                    1 => (0, 0, width - 1, height),
+                    2 => (0, 1, width, height - 1),
+                    _ => (0, 0, width, height - 1),
+                };
+                match scenario.cropped(rect.0, rect.1, rect.2, rect.3).filter(&mut fails) {
+                    Some(cropped) => {
+                        scenario = cropped;
+                        shrunk = true;
+                    }
+                    None => break,
+                }
+            }
+        }
+        for pos in scenario.game().positions().collect::<Vec<_>>() {
+            if let Some(grassed) = scenario.grassed(pos).filter(&mut fails) {
+                scenario = grassed;
+                shrunk = true;
+            }
+        }
+        if !shrunk {
+            return (scenario, n_tried);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (name, scenario) = match load(&args) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("minimize: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let failures = scenario.failures();
+    if failures.is_empty() {
+        eprintln!("minimize: {} does not fail, there is nothing to keep", name);
+        return ExitCode::FAILURE;
+    }
+    let before = (scenario.width, scenario.height, scenario.earlier.len() + 1);
+    let (smallest, n_tried) = minimize(scenario, &failures);
+    let n_grass = smallest.tiles.iter().filter(|tile| tile[0] == 0).count();
+    println!("# Minimized from {}, {} scenarios tried", name, n_tried);
+    println!("# {}x{} and {} maps down to {}x{} and {} maps, {} grass tiles", before.0, before.1, before.2, smallest.width, smallest.height, smallest.earlier.len() + 1, n_grass);
+    println!("# Still failing: {}", failures.join(", "));
+    print!("{}", smallest);
+    ExitCode::SUCCESS
+}
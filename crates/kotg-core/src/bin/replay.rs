@@ -0,0 +1,152 @@
+// Post-mortem of a recorded game: the current bot reads each turn's input again and what it would
+// play is printed next to what was played.
+//
+//   cargo run -q -p kotg-core --bin replay -- [--seed N] [--compare A B] <file> [player]
+//
+// The file is a replay written by the arena or the referee, or one downloaded from CodinGame where
+// one of the agents is this bot, player 0 by default. Messages are left out of the comparison.
+// The bot is seeded the way the arena seeded the player from the game's seed, so on an arena
+// replay an unchanged bot plays every turn the same. On a CodinGame replay it takes the seed the
+// bot logged. Bots the referee started picked their own seed, --seed gives it.
+// With --compare, two of the referee's presets read the same inputs instead and the turns they
+// answer differently are printed side by side. Each answer is played a turn ahead on the turn's
+// board against what the opponent really played, and the evaluation of the board it leaves is
+// printed from the player's side, with B's minus A's. The inputs stay the recorded ones, so after
+// the first difference both play on boards neither of them led to.
+use std::fs;
+use std::io::Cursor;
+use std::process::ExitCode;
+
+use kotg_core::analysis::Analysis;
+use kotg_core::io::{self, InputStatus};
+use kotg_core::json::Json;
+use kotg_core::referee::{bot_seed, preset, presets, Bot, StrategyBot};
+use kotg_core::replay::{RecordedTurn, Replay};
+use kotg_core::{parse_actions, Action, Config, GameState};
+
+// The line's actions in a fixed order, or the line itself when it does not parse
+fn comparable(line: &str) -> Result<Vec<String>, String> {
+    let actions = parse_actions(line).map_err(|_| line.trim().to_string())?;
+    let mut actions: Vec<String> = actions.iter().filter(|action| !matches!(action, Action::Message { .. } | Action::Wait)).map(Action::to_string).collect();
+    actions.sort();
+    Ok(actions)
+}
+
+// The given seed, else the one the arena derived from the game's, else the one the bot logged
+// in a CodinGame replay
+fn seed(given: Option<&String>, json: &Json, player: usize) -> Result<u64, String> {
+    match given {
+        Some(seed) => seed.parse().map_err(|_| format!("--seed {:?} is not a number", seed)),
+        None => Replay::recorded_seed(json)
+            .map(|seed| bot_seed(seed, player))
+            .or_else(|| Replay::logged_seed(json))
+            .ok_or_else(|| "the file has no seed, --seed gives one".to_string()),
+    }
+}
+
+// The values following the flag, all removed from the arguments
+fn take_flag(args: &mut Vec<String>, flag: &str, n_values: usize) -> Result<Option<Vec<String>>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(k) if k + n_values < args.len() => {
+            args.remove(k);
+            Ok(Some(args.drain(k..k + n_values).collect()))
+        }
+        Some(_) => Err(format!("{} needs {} values", flag, n_values)),
+        None => Ok(None),
+    }
+}
+
+// The evaluation of the board after the line, against the opponent's, from the player's side
+fn evaluation_after(game: &GameState, line: &str, opponent_line: &str) -> i32 {
+    let actions = |line: &str| parse_actions(line).unwrap_or_default();
+    Analysis::new(&game.simulate(&actions(line), &actions(opponent_line))).evaluation()
+}
+
+fn compare(presets: [(&str, Config); 2], seed: u64, turns: &[RecordedTurn], opponent_turns: &[RecordedTurn]) {
+    let mut bots = presets.map(|(_, config)| StrategyBot::new(Config { seed: Some(seed), ..config }));
+    let mut game: Option<GameState> = None;
+    let (mut n_different, mut total_delta) = (0, 0);
+    for (turn, opponent_turn) in turns.iter().zip(opponent_turns) {
+        let lines = bots.each_mut().map(|bot| bot.play(&turn.input));
+        // The board the bots read, kept the way they keep theirs
+        let mut input = Cursor::new(turn.input.as_str());
+        if game.is_none() {
+            game = io::read_game(&mut input).ok().map(|config| GameState::new(&config));
+        }
+        let Some(game) = game.as_mut() else {
+            println!("turn {}: the input does not start with the map size", turn.turn);
+            continue;
+        };
+        if !matches!(io::read_turn(&mut input, game), InputStatus::Ready) {
+            println!("turn {}: the input does not read as a turn", turn.turn);
+            continue;
+        }
+        if comparable(&lines[0]) == comparable(&lines[1]) {
+            continue;
+        }
+        n_different += 1;
+        let evaluations = lines.each_ref().map(|line| evaluation_after(game, line, &opponent_turn.stdout));
+        total_delta += evaluations[1] - evaluations[0];
+        println!("turn {}:", turn.turn);
+        let width = presets.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for k in 0..2 {
+            println!("  {:width$}  evaluation {:+}  {}", presets[k].0, evaluations[k], lines[k], width = width);
+        }
+        println!("  {} minus {}: {:+}", presets[1].0, presets[0].0, evaluations[1] - evaluations[0]);
+    }
+    println!("{} turns, {} played differently, {} minus {} summed {:+}", turns.len(), n_different, presets[1].0, presets[0].0, total_delta);
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let (given_seed, compared) = match (take_flag(&mut args, "--seed", 1), take_flag(&mut args, "--compare", 2)) {
+        (Ok(given_seed), Ok(compared)) => (given_seed.map(|mut values| values.remove(0)), compared),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("replay: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let player = args.get(1).map_or(Some(0), |player| player.parse::<usize>().ok().filter(|&player| player < 2));
+    let (Some(path), Some(player), 1..=2) = (args.first(), player, args.len()) else {
+        eprintln!("replay: usage: replay [--seed N] [--compare A B] <file> [player 0 or 1]");
+        return ExitCode::FAILURE;
+    };
+    let presets = match compared.as_deref().map(|names| names.iter().map(|name| preset(name).map(|config| (name.as_str(), config)).ok_or(name)).collect::<Result<Vec<_>, _>>()) {
+        Some(Ok(presets)) => Some([presets[0], presets[1]]),
+        Some(Err(name)) => {
+            let names: Vec<&str> = presets().into_iter().map(|(name, _)| name).collect();
+            eprintln!("replay: no preset is called {:?}, the presets are {}", name, names.join(", "));
+            return ExitCode::FAILURE;
+        }
+        None => None,
+    };
+    let recorded = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read {}: {}", path, err))
+        .and_then(|text| text.parse::<Json>().map_err(|err| err.to_string()))
+        .and_then(|json| Ok((Replay::recorded_turns(&json, player)?, Replay::recorded_turns(&json, 1 - player)?, seed(given_seed.as_ref(), &json, player)?)));
+    let (turns, opponent_turns, seed) = match recorded {
+        Ok(recorded) => recorded,
+        Err(err) => {
+            eprintln!("replay: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("bot seed {}", seed);
+    if let Some(presets) = presets {
+        compare(presets, seed, &turns, &opponent_turns);
+        return ExitCode::SUCCESS;
+    }
+    let mut bot = StrategyBot::new(Config { seed: Some(seed), ..Config::default() });
+    let mut n_different = 0;
+    for turn in turns.iter() {
+        let would_play = bot.play(&turn.input);
+        if comparable(&would_play) == comparable(&turn.stdout) {
+            println!("turn {}: same", turn.turn);
+        } else {
+            n_different += 1;
+            println!("turn {}:\n  played      {}\n  would play  {}", turn.turn, turn.stdout, would_play);
+        }
+    }
+    println!("{} turns, {} played differently", turns.len(), n_different);
+    ExitCode::SUCCESS
+}
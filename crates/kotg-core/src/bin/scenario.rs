@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use kotg_core::format_actions;
+use kotg_core::scenario::Scenario;
+
+const SCENARIO_DIR: &str = "scenarios";
+
+// Runs the scenario files given as arguments, or every file in scenarios/
+fn main() -> ExitCode {
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        match fs::read_dir(SCENARIO_DIR) {
+            Ok(entries) => paths = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+            Err(err) => {
+                eprintln!("cannot list {}: {}", SCENARIO_DIR, err);
+                return ExitCode::FAILURE;
+            }
+        }
+        paths.sort();
+    }
+    let mut n_failed = 0;
+    for path in paths.iter() {
+        let scenario = match fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|text| text.parse::<Scenario>().map_err(|err| err.to_string())) {
+            Ok(scenario) => scenario,
+            Err(err) => {
+                println!("ERROR {}: {}", path.display(), err);
+                n_failed += 1;
+                continue;
+            }
+        };
+        if let Some(mismatches) = scenario.check_simulation() {
+            if mismatches.is_empty() {
+                println!("ok    {}", path.display());
+            }
+            else {
+                n_failed += 1;
+                println!("FAIL  {}: simulated turn", path.display());
+                for mismatch in mismatches {
+                    println!("      {}", mismatch);
+                }
+            }
+            continue;
+        }
+        let (actions, failed) = scenario.run();
+        if failed.is_empty() {
+            println!("ok    {}", path.display());
+            continue;
+        }
+        n_failed += 1;
+        println!("FAIL  {}: played {}", path.display(), format_actions(&actions));
+        for expectation in failed {
+            println!("      expect {}, counted {}", expectation.text, expectation.count(&actions));
+        }
+    }
+    println!("{} scenarios, {} failed", paths.len(), n_failed);
+    if n_failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,327 @@
+// Random games against the simulator's invariants, where scenarios check single positions and
+// tests/simulator.rs single turns on generated boards:
+//
+//   cargo run -q -p kotg-core --bin simcheck -- [games] [first seed]
+//
+// Each game starts on a generated map and both players play random actions, long MOVE targets
+// and oversized moves included, mixed with actions the referee must turn away: moves from
+// someone else's tiles, builds on occupied tiles or grass, spawns on recyclers, grass or someone
+// else's tiles. After every turn the new board is checked:
+// - the turned away actions change nothing, the turn plays as if they were not there
+// - scrap drops by one on the tiles a recycler reaches and stays put elsewhere
+// - grass holds no units, no recycler and no owner
+// - a tile changes owner only by units standing on it or by turning to grass
+// - a recycler appears only where it was built, on its builder's tile
+// - no player ends with more units than they had plus those they spawned
+// - matter is what was there, minus what was spent, plus the base income and one for each tile
+//   the player's recyclers reach, counted here from the board rather than by the simulator
+// - undo brings the board back exactly
+// Each board is also checked on its own: among builds and spawns anywhere and moves to a passable
+// neighbor, amounts up to MAX_AMOUNT, validate accepts exactly what legal_actions lists, and a few
+// of those actions played alone give the same board through apply_delta as through simulate.
+// The referee's end-of-game audit of the board counts the same tile difference as the evaluation,
+// and scores every tile for the other player on the board the enemy sees.
+// A failing turn is shrunk to the fewest actions that still fail, then printed with its seed in
+// the scenario format, ready to become a scenario file once the expected board is written in.
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+use kotg_core::analysis::Analysis;
+use kotg_core::ascii::format_tile;
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::referee::{audit, board_seen_by};
+use kotg_core::sim::BASE_INCOME;
+use kotg_core::{format_actions, Action, ActionSets, Amount, GameState, Owner, Pos, Rng};
+
+const DEFAULT_GAMES: u64 = 200;
+const MAX_TURNS: usize = 60;
+const PLAYERS: [Owner; 2] = [Owner::Me, Owner::Enemy];
+const COST: i32 = 10;
+const MAX_AMOUNT: i32 = 3;
+const LEGAL_SAMPLE: usize = 8;
+
+fn matter(game: &GameState, p: usize) -> i32 {
+    if p == 0 { game.my_matter } else { game.enemy_matter }
+}
+
+fn units_of(game: &GameState, p: usize) -> i32 {
+    game.iter_owned(PLAYERS[p]).map(|(_, loc)| loc.units).sum()
+}
+
+// Actions the referee turns away whatever else is played, `built` being p's accepted builds
+fn rejected(game: &GameState, p: usize, action: &Action, built: &[Pos]) -> bool {
+    match *action {
+        Action::Move { from, .. } => game.loc(from).owner != PLAYERS[p] || game.loc(from).units == 0,
+        Action::Build { at } => {
+            let loc = game.loc(at);
+            loc.owner != PLAYERS[p] || loc.units > 0 || loc.recycler || loc.scrap_amount == 0
+        }
+        Action::Spawn { at, .. } => {
+            let loc = game.loc(at);
+            loc.owner != PLAYERS[p] || loc.recycler || loc.scrap_amount == 0 || built.contains(&at)
+        }
+        Action::Wait | Action::Message { .. } => false,
+    }
+}
+
+// The accepted builds among p's actions
+fn builds(game: &GameState, p: usize, actions: &[Action]) -> Vec<Pos> {
+    actions
+        .iter()
+        .filter(|action| !rejected(game, p, action, &[]))
+        .filter_map(|action| if let Action::Build { at } = *action { Some(at) } else { None })
+        .collect()
+}
+
+// Player p's actions: ones the referee accepts, spending no more than they have, and a few it
+// turns away slipped in among them
+fn random_actions(game: &GameState, p: usize, rng: &mut Rng) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut budget = matter(game, p);
+    let mut built = Vec::new();
+    let positions: Vec<Pos> = game.positions().collect();
+    for (pos, loc) in game.iter_owned(PLAYERS[p]) {
+        if loc.units > 0 && rng.gen_range(0..3) > 0 {
+            // Past the tile's units now and then, the referee moves what is there
+            let Some(amount) = Amount::new(rng.gen_range(1..loc.units + 2)) else { continue };
+            let to = if rng.gen_range(0..2) == 0 {
+                positions[rng.gen_range(0..positions.len() as i32) as usize]
+            } else {
+                let neighbors: Vec<Pos> = game.neighbors(pos).collect();
+                neighbors[rng.gen_range(0..neighbors.len() as i32) as usize]
+            };
+            actions.push(Action::Move { amount, from: pos, to });
+        }
+        let buildable = loc.units == 0 && !loc.recycler && loc.scrap_amount > 0;
+        if buildable && budget >= COST && rng.gen_range(0..8) == 0 {
+            actions.push(Action::Build { at: pos });
+            built.push(pos);
+            budget -= COST;
+        }
+    }
+    for (pos, loc) in game.iter_owned(PLAYERS[p]) {
+        if !loc.recycler && loc.scrap_amount > 0 && !built.contains(&pos) && budget >= COST && rng.gen_range(0..4) == 0 {
+            let n = rng.gen_range(1..budget / COST + 1).min(3);
+            let Some(amount) = Amount::new(n) else { continue };
+            actions.push(Action::Spawn { amount, at: pos });
+            budget -= COST * n;
+        }
+    }
+    for _ in 0..rng.gen_range(0..4) {
+        let at = positions[rng.gen_range(0..positions.len() as i32) as usize];
+        let action = match rng.gen_range(0..3) {
+            0 => Action::Move { amount: Amount::new(1).expect("1 is a valid amount"), from: at, to: positions[0] },
+            1 => Action::Build { at },
+            _ => Action::Spawn { amount: Amount::new(1).expect("1 is a valid amount"), at },
+        };
+        if rejected(game, p, &action, &built) {
+            let k = rng.gen_range(0..actions.len() as i32 + 1) as usize;
+            actions.insert(k, action);
+        }
+    }
+    actions
+}
+
+// The invariants the turn broke, empty when it is fine
+fn violations(before: &GameState, actions: &ActionSets) -> Vec<String> {
+    let mut broken = Vec::new();
+    let built_by = [builds(before, 0, &actions.mine), builds(before, 1, &actions.enemy)];
+    let accepted = |p: usize, played: &[Action]| -> Vec<Action> { played.iter().filter(|action| !rejected(before, p, action, &built_by[p])).cloned().collect() };
+    let legal = ActionSets { mine: accepted(0, &actions.mine), enemy: accepted(1, &actions.enemy) };
+    let after = before.apply(&legal);
+    if before.apply(actions) != after {
+        broken.push("actions the referee turns away changed the turn".to_string());
+    }
+    let played = [&legal.mine, &legal.enemy];
+    let built: Vec<(Pos, usize)> = (0..2).flat_map(|p| built_by[p].iter().map(move |&at| (at, p))).collect();
+    let (width, height) = (before.width(), before.height());
+    let reach = |p: usize| -> Vec<Pos> {
+        let recyclers: Vec<Pos> = before
+            .iter_owned(PLAYERS[p])
+            .filter(|(_, loc)| loc.recycler)
+            .map(|(pos, _)| pos)
+            .chain(built_by[p].iter().copied())
+            .collect();
+        let mut reached: Vec<Pos> = recyclers
+            .iter()
+            .flat_map(|&pos| std::iter::once(pos).chain(pos.neighbors(width, height)))
+            .filter(|&pos| before.loc(pos).scrap_amount > 0)
+            .collect();
+        reached.sort_by_key(|pos| pos.index(width));
+        reached.dedup();
+        reached
+    };
+    let reached = [reach(0), reach(1)];
+    for pos in before.positions() {
+        let (was, now) = (before.loc(pos), after.loc(pos));
+        let harvested = reached.iter().any(|tiles| tiles.contains(&pos));
+        if now.scrap_amount != was.scrap_amount - harvested as i32 {
+            broken.push(format!("{:?} scrap went from {} to {}", pos, was.scrap_amount, now.scrap_amount));
+        }
+        if now.scrap_amount == 0 && (now.units != 0 || now.recycler || now.owner != Owner::Neutral) {
+            broken.push(format!("{:?} is grass with {}", pos, format_tile(now)));
+        }
+        if now.units < 0 || (now.units > 0 && now.owner == Owner::Neutral) {
+            broken.push(format!("{:?} has units {} for {:?}", pos, now.units, now.owner));
+        }
+        if now.owner != was.owner && now.units == 0 && now.scrap_amount > 0 {
+            broken.push(format!("{:?} went from {:?} to {:?} with no unit on it", pos, was.owner, now.owner));
+        }
+        if now.recycler && !was.recycler && !built.iter().any(|&(at, p)| at == pos && now.owner == PLAYERS[p]) {
+            broken.push(format!("{:?} has a recycler nobody built", pos));
+        }
+    }
+    for p in 0..2 {
+        let spawned: i32 = played[p].iter().map(|action| if let Action::Spawn { amount, .. } = action { amount.get() } else { 0 }).sum();
+        if units_of(&after, p) > units_of(before, p) + spawned {
+            broken.push(format!("player {} went from {} units to {} spawning {}", p, units_of(before, p), units_of(&after, p), spawned));
+        }
+        let n_built = built_by[p].len() as i32;
+        let expected = matter(before, p) - COST * (n_built + spawned) + BASE_INCOME + reached[p].len() as i32;
+        if matter(&after, p) != expected {
+            broken.push(format!("player {} has {} matter, expected {}", p, matter(&after, p), expected));
+        }
+    }
+    let mut undone = before.clone();
+    let delta = undone.apply_delta(&legal);
+    if undone != after {
+        broken.push("apply_delta disagrees with simulate".to_string());
+    }
+    undone.undo(delta);
+    if undone != *before {
+        broken.push("undo did not restore the board".to_string());
+    }
+    broken
+}
+
+// Where legal_actions disagrees with validate or one of its actions with the simulator
+fn legal_violations(game: &GameState, rng: &mut Rng) -> Vec<String> {
+    let legal = game.legal_actions(MAX_AMOUNT);
+    let listed: HashSet<String> = legal.iter().map(Action::to_string).collect();
+    let amounts = (1..=MAX_AMOUNT).filter_map(Amount::new);
+    let mut candidates = vec![Action::Wait];
+    for at in game.positions() {
+        candidates.push(Action::Build { at });
+        candidates.extend(amounts.clone().map(|amount| Action::Spawn { amount, at }));
+        for to in game.neighbors(at).filter(|&to| game.passable(to)) {
+            candidates.extend(amounts.clone().map(|amount| Action::Move { amount, from: at, to }));
+        }
+    }
+    let mut broken: Vec<String> = candidates
+        .iter()
+        .filter_map(|action| match (game.validate(action).is_ok(), listed.contains(&action.to_string())) {
+            (true, false) => Some(format!("{} is accepted by validate but left out by legal_actions", action)),
+            (false, true) => Some(format!("{} is listed by legal_actions but rejected by validate", action)),
+            _ => None,
+        })
+        .collect();
+    // Listed actions outside the candidates, a move to grass for one
+    let candidates: HashSet<String> = candidates.iter().map(Action::to_string).collect();
+    broken.extend(legal.iter().filter(|action| !candidates.contains(&action.to_string())).map(|action| format!("{} is listed by legal_actions but is no candidate", action)));
+    for _ in 0..LEGAL_SAMPLE.min(legal.len()) {
+        let action = &legal[rng.gen_range(0..legal.len() as i32) as usize];
+        let alone = ActionSets { mine: vec![action.clone()], enemy: Vec::new() };
+        let mut undone = game.clone();
+        let delta = undone.apply_delta(&alone);
+        if undone != game.apply(&alone) {
+            broken.push(format!("{} alone: apply_delta disagrees with simulate", action));
+        }
+        undone.undo(delta);
+        if undone != *game {
+            broken.push(format!("{} alone: undo did not restore the board", action));
+        }
+    }
+    broken
+}
+
+// Where the referee's end-of-game audit of the board disagrees with the evaluation's tile term,
+// or with itself on the board the enemy sees
+fn audit_violations(game: &GameState) -> Vec<String> {
+    let mut broken = Vec::new();
+    let owners = audit(game);
+    let scores = [0, 1].map(|p| owners.iter().filter(|&&owner| owner == Some(p)).count() as i32);
+    let tile_term = Analysis::new(game).evaluation() - (units_of(game, 0) - units_of(game, 1)) - (matter(game, 0) - matter(game, 1)) / COST;
+    if tile_term != scores[0] - scores[1] {
+        broken.push(format!("the evaluation counts {} tiles ahead, the audit {} to {}", tile_term, scores[0], scores[1]));
+    }
+    let swapped = audit(&board_seen_by(game, 1));
+    if let Some(pos) = game.positions().find(|&pos| owners[pos].map(|p| 1 - p) != swapped[pos]) {
+        broken.push(format!("{:?} scores for {:?}, for {:?} seen by the enemy", pos, owners[pos], swapped[pos]));
+    }
+    broken
+}
+
+// Drops actions one at a time for as long as the turn keeps failing
+fn shrink(before: &GameState, mut actions: ActionSets) -> ActionSets {
+    loop {
+        let n_actions = actions.mine.len() + actions.enemy.len();
+        let smaller = (0..n_actions).find_map(|k| {
+            let mut candidate = ActionSets { mine: actions.mine.clone(), enemy: actions.enemy.clone() };
+            if k < candidate.mine.len() {
+                candidate.mine.remove(k);
+            } else {
+                candidate.enemy.remove(k - actions.mine.len());
+            }
+            if violations(before, &candidate).is_empty() { None } else { Some(candidate) }
+        });
+        match smaller {
+            Some(candidate) => actions = candidate,
+            None => return actions,
+        }
+    }
+}
+
+fn print_board(game: &GameState) {
+    println!("turn {}\nmatter {} {}\nmap", game.turn, game.my_matter, game.enemy_matter);
+    for row in game.grid.rows() {
+        println!("{}", row.iter().map(|loc| format!("{:<6}", format_tile(loc))).collect::<String>().trim_end());
+    }
+    println!("end");
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parse = |k: usize, default: u64| args.get(k).map_or(Ok(default), |arg| arg.parse::<u64>().map_err(|_| arg.clone()));
+    let (n_games, first_seed) = match (parse(0, DEFAULT_GAMES), parse(1, 0)) {
+        (Ok(n_games), Ok(first_seed)) => (n_games, first_seed),
+        (Err(arg), _) | (_, Err(arg)) => {
+            eprintln!("simcheck: {:?} is not a number, usage: simcheck [games] [first seed]", arg);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut n_turns = 0;
+    for seed in first_seed..first_seed + n_games {
+        let mut game = MapGenerator::generate(seed);
+        let mut rng = Rng::new(seed);
+        for _ in 0..MAX_TURNS {
+            let mut broken = legal_violations(&game, &mut rng);
+            broken.extend(audit_violations(&game));
+            if !broken.is_empty() {
+                println!("# FAIL seed {} turn {}", seed, game.turn);
+                print_board(&game);
+                for broken in broken {
+                    println!("#   {}", broken);
+                }
+                return ExitCode::FAILURE;
+            }
+            let actions = ActionSets { mine: random_actions(&game, 0, &mut rng), enemy: random_actions(&game, 1, &mut rng) };
+            if !violations(&game, &actions).is_empty() {
+                let actions = shrink(&game, actions);
+                println!("# FAIL seed {} turn {}", seed, game.turn);
+                print_board(&game);
+                println!("play {}\nenemy {}", format_actions(&actions.mine), format_actions(&actions.enemy));
+                for broken in violations(&game, &actions) {
+                    println!("#   {}", broken);
+                }
+                return ExitCode::FAILURE;
+            }
+            game = game.apply(&actions);
+            n_turns += 1;
+            if game.iter_owned(Owner::Me).next().is_none() || game.iter_owned(Owner::Enemy).next().is_none() {
+                break;
+            }
+        }
+    }
+    println!("{} games, {} turns, no invariant broken", n_games, n_turns);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,129 @@
+// The bot must play the same from either side of the map:
+//
+//   cargo run -q -p kotg-core --bin symmetry -- [games] [first seed]
+//
+// Each seed plays two games. The first is a mirror match, the bot against itself with the same
+// seed on both sides. Generated maps are symmetric through their center, so every turn player 1
+// must play the mirror of player 0's line, the board must stay its own mirror with the owners
+// swapped, and the game must end in a draw. The second is an arena game, each side seeded on its
+// own so the boards get lopsided, and every board either player gets is checked on its own:
+// - its mirror evaluates the same, and its mirror seen by the enemy the opposite
+// - a fresh bot plans on its mirror the mirror of what it plans on it
+// The first turn that breaks one is printed with its seed.
+use std::process::ExitCode;
+
+use kotg_core::ascii::format_tile;
+use kotg_core::mapgen::MapGenerator;
+use kotg_core::referee::{board_seen_by, bot_seed, Bot, Referee, StrategyBot};
+use kotg_core::{format_actions, parse_actions, Action, Analysis, Config, GameState, GreedyOutsideStrategy, Strategy};
+
+const DEFAULT_GAMES: u64 = 20;
+
+fn config(seed: u64) -> Config {
+    Config { seed: Some(seed), ..Config::default() }
+}
+
+// What breaks the symmetry of the board on its own
+fn board_violations(game: &GameState, seed: u64) -> Vec<String> {
+    let mut broken = Vec::new();
+    let (width, height) = (game.width(), game.height());
+    let mirrored = game.mirrored();
+    let evaluation = Analysis::new(game).evaluation();
+    let mirrored_evaluation = Analysis::new(&mirrored).evaluation();
+    let enemy_evaluation = Analysis::new(&board_seen_by(&mirrored, 1)).evaluation();
+    if mirrored_evaluation != evaluation || enemy_evaluation != -evaluation {
+        broken.push(format!("evaluation {}, {} mirrored, {} mirrored and seen by the enemy", evaluation, mirrored_evaluation, enemy_evaluation));
+    }
+    let plan = |board: &GameState| GreedyOutsideStrategy::new(board, config(seed)).plan(board);
+    let planned = plan(game);
+    let mirror_planned: Vec<Action> = plan(&mirrored).iter().map(|action| action.mirrored(width, height)).collect();
+    if planned != mirror_planned {
+        broken.push(format!("planned {}\n#   on the mirror, turned back: {}", format_actions(&planned), format_actions(&mirror_planned)));
+    }
+    broken
+}
+
+// Tiles that differ from their mirror with the owners swapped, as seen by both players
+fn asymmetric_tiles(left: &GameState, right: &GameState) -> Vec<String> {
+    let seen_from_left = right.mirrored();
+    let mut broken: Vec<String> = left
+        .positions()
+        .filter(|&pos| left.loc(pos) != seen_from_left.loc(pos))
+        .map(|pos| format!("{:?} is {} for player 0, its mirror {} for player 1", pos, format_tile(left.loc(pos)), format_tile(seen_from_left.loc(pos))))
+        .collect();
+    if (left.my_matter, left.enemy_matter) != (right.my_matter, right.enemy_matter) {
+        broken.push(format!("matter {}/{} for player 0, {}/{} for player 1", left.my_matter, left.enemy_matter, right.my_matter, right.enemy_matter));
+    }
+    broken
+}
+
+// The first broken symmetry of the game, with its turn
+fn mirror_match(seed: u64) -> Result<usize, (usize, Vec<String>)> {
+    let mut referee = Referee::new(&MapGenerator::generate(seed));
+    let mut bots = [config(seed), config(seed)].map(StrategyBot::new);
+    while !referee.is_over() {
+        let turn = referee.turns() + 1;
+        let left = referee.view(0);
+        let mut broken = asymmetric_tiles(left, referee.view(1));
+        let inputs = [referee.input(0), referee.input(1)];
+        let outputs = [bots[0].play(&inputs[0]), bots[1].play(&inputs[1])];
+        let (width, height) = (left.width(), left.height());
+        let mirrored = parse_actions(&outputs[0]).map(|actions| actions.iter().map(|action| action.mirrored(width, height)).collect::<Vec<Action>>());
+        if mirrored != parse_actions(&outputs[1]) {
+            broken.push(format!("player 0 played {}\n#   player 1 played {}", outputs[0], outputs[1]));
+        }
+        if !broken.is_empty() {
+            return Err((turn, broken));
+        }
+        referee.step([outputs[0].as_str(), outputs[1].as_str()]);
+    }
+    let result = referee.result().expect("the game is over");
+    match result.winner() {
+        None => Ok(result.turns),
+        Some(player) => Err((result.turns, vec![format!("player {} won {:?}, {} to {}", player, result.end, result.scores[0], result.scores[1])])),
+    }
+}
+
+// The first board of an arena game that breaks symmetry on its own
+fn arena_boards(seed: u64) -> Result<usize, (usize, Vec<String>)> {
+    let mut referee = Referee::new(&MapGenerator::generate(seed));
+    let mut bots = [0, 1].map(|player| StrategyBot::new(config(bot_seed(seed, player))));
+    while !referee.is_over() {
+        let turn = referee.turns() + 1;
+        let broken: Vec<String> = (0..2).flat_map(|player| board_violations(referee.view(player), seed).into_iter().map(move |broken| format!("player {}: {}", player, broken))).collect();
+        if !broken.is_empty() {
+            return Err((turn, broken));
+        }
+        let inputs = [referee.input(0), referee.input(1)];
+        let outputs = [bots[0].play(&inputs[0]), bots[1].play(&inputs[1])];
+        referee.step([outputs[0].as_str(), outputs[1].as_str()]);
+    }
+    Ok(referee.turns())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parse = |k: usize, default: u64| args.get(k).map_or(Ok(default), |arg| arg.parse::<u64>().map_err(|_| arg.clone()));
+    let (n_games, first_seed) = match (parse(0, DEFAULT_GAMES), parse(1, 0)) {
+        (Ok(n_games), Ok(first_seed)) => (n_games, first_seed),
+        (Err(arg), _) | (_, Err(arg)) => {
+            eprintln!("symmetry: {:?} is not a number, usage: symmetry [games] [first seed]", arg);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut n_turns = 0;
+    for seed in first_seed..first_seed + n_games {
+        match mirror_match(seed).and_then(|mirror_turns| Ok(mirror_turns + arena_boards(seed)?)) {
+            Ok(turns) => n_turns += turns,
+            Err((turn, broken)) => {
+                println!("# FAIL seed {} turn {}", seed, turn);
+                for broken in broken {
+                    println!("#   {}", broken);
+                }
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    println!("{} seeds, {} turns, every mirror match symmetric and drawn, every board symmetric", n_games, n_turns);
+    ExitCode::SUCCESS
+}
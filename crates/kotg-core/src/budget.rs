@@ -0,0 +1,92 @@
+pub const UNIT_COST: i32 = 10;
+pub const RECYCLER_COST: i32 = 10;
+
+// The turn's matter shared by the planning phases. Matter goes from free to reserved when a phase
+// plans an action, and from reserved to spent once the action is validated. Part of the free
+// matter can be earmarked as a defensive reserve that only try_reserve_earmarked draws from, and
+// part saved for a later turn, which nothing draws from. Reserved, spent, earmarked and saved
+// together never exceed the matter the turn started with.
+#[derive(Debug, Clone)]
+pub struct MatterBudget {
+    total: i32,
+    reserved: i32,
+    spent: i32,
+    earmarked: i32,
+    saved: i32,
+}
+
+impl MatterBudget {
+    pub fn new(total: i32) -> Self {
+        MatterBudget { total: total.max(0), reserved: 0, spent: 0, earmarked: 0, saved: 0 }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.total
+    }
+
+    pub fn reserved(&self) -> i32 {
+        self.reserved
+    }
+
+    pub fn spent(&self) -> i32 {
+        self.spent
+    }
+
+    pub fn earmarked(&self) -> i32 {
+        self.earmarked
+    }
+
+    pub fn saved(&self) -> i32 {
+        self.saved
+    }
+
+    // Matter neither reserved, spent, earmarked nor saved
+    pub fn available(&self) -> i32 {
+        self.total - self.reserved - self.spent - self.earmarked - self.saved
+    }
+
+    // How many units the available matter still pays for
+    pub fn affordable_units(&self) -> i32 {
+        self.available() / UNIT_COST
+    }
+
+    pub fn try_reserve(&mut self, cost: i32) -> bool {
+        if cost < 0 || cost > self.available() {
+            return false;
+        }
+        self.reserved += cost;
+        true
+    }
+
+    // Sets aside up to `amount` of the available matter as the defensive reserve
+    pub fn earmark(&mut self, amount: i32) {
+        self.earmarked += amount.clamp(0, self.available());
+    }
+
+    // Keeps up to `amount` of the available matter for a later turn
+    pub fn save(&mut self, amount: i32) {
+        self.saved += amount.clamp(0, self.available());
+    }
+
+    pub fn try_reserve_earmarked(&mut self, cost: i32) -> bool {
+        if cost < 0 || cost > self.earmarked {
+            return false;
+        }
+        self.earmarked -= cost;
+        self.reserved += cost;
+        true
+    }
+
+    // Gives back matter reserved for an action that was dropped
+    pub fn release(&mut self, cost: i32) {
+        debug_invariant!(cost <= self.reserved, "releasing {} of {} reserved matter", cost, self.reserved);
+        self.reserved -= cost.min(self.reserved);
+    }
+
+    pub fn spend(&mut self, cost: i32) {
+        debug_invariant!(cost <= self.reserved, "spending {} of {} reserved matter", cost, self.reserved);
+        let cost = cost.min(self.reserved);
+        self.reserved -= cost;
+        self.spent += cost;
+    }
+}
@@ -0,0 +1,11 @@
+// Written by `kotg-tuner --seed 0 --write`: scored 0.762 against the Config before it over 40 games.
+// Run the tuner again rather than editing it.
+use crate::strategy::{Config, DirectionalPrior};
+
+pub(crate) const DEFAULT_CONFIG: Config = Config {
+    expansion_prior: DirectionalPrior { toward_enemy: 1.0, toward_center: 0.0, along_rows: 0.0 },
+    defense_margin_range: (1, 3),
+    seed: None,
+    exploration: 0.0,
+    analysis_budget_ms: None,
+};
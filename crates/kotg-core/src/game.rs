@@ -0,0 +1,430 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::actions::{Action, Amount};
+use crate::grid::Grid;
+use crate::pos::Pos;
+use crate::zobrist;
+
+pub const UNREACHABLE: u16 = u16::MAX;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Owner {
+    #[default]
+    Neutral,
+    Me,
+    Enemy,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseOwnerError {
+    UnknownCode(i32),
+}
+
+impl fmt::Display for ParseOwnerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownCode(code) => write!(f, "unknown owner code {code}"),
+        }
+    }
+}
+
+// The referee's owner codes: 1 = me, 0 = foe, -1 = neutral
+impl TryFrom<i32> for Owner {
+    type Error = ParseOwnerError;
+
+    fn try_from(code: i32) -> Result<Self, Self::Error> {
+        match code {
+            -1 => Ok(Owner::Neutral),
+            0 => Ok(Owner::Enemy),
+            1 => Ok(Owner::Me),
+            _ => Err(ParseOwnerError::UnknownCode(code)),
+        }
+    }
+}
+
+impl Owner {
+    pub fn to_code(self) -> i32 {
+        match self {
+            Owner::Neutral => -1,
+            Owner::Enemy => 0,
+            Owner::Me => 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub scrap_amount: i32,
+    pub owner: Owner,
+    pub units: i32,
+    pub recycler: bool,
+    pub can_build: bool,
+    pub can_spawn: bool,
+    pub in_range_of_recycler: bool,
+}
+
+impl Location {
+    // Turns the recycler on the tile keeps harvesting, None without one. Its own tile loses
+    // one scrap each turn, and the recycler goes with the last of it.
+    pub fn recycler_turns_remaining(&self) -> Option<i32> {
+        if self.recycler { Some(self.scrap_amount) } else { None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+// Why the referee would reject an action
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ActionError {
+    OutOfBounds(Pos),
+    CannotBuild(Pos),
+    CannotSpawn(Pos),
+    NotEnoughUnits { at: Pos, needed: i32, available: i32 },
+    NotEnoughMatter { needed: i32, available: i32 },
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfBounds(pos) => write!(f, "{:?} is off the map", pos),
+            Self::CannotBuild(pos) => write!(f, "cannot build on {:?}", pos),
+            Self::CannotSpawn(pos) => write!(f, "cannot spawn on {:?}", pos),
+            Self::NotEnoughUnits { at, needed, available } => write!(f, "{needed} units needed on {:?}, {available} there", at),
+            Self::NotEnoughMatter { needed, available } => write!(f, "{needed} matter needed, {available} left"),
+        }
+    }
+}
+
+// What the referee sends once before the first turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+}
+
+// The board as the referee last described it, cheap to copy for planners that branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameState {
+    pub turn: usize,
+    pub my_side: Option<Side>,
+    pub grid: Grid<Location>,
+    pub my_matter: i32,
+    pub enemy_matter: i32,
+    pub my_robots: Vec<Pos>,
+    // Tiles where the last update changed scrap, owner, units or recycler
+    pub changed: Vec<Pos>,
+    // Kept up to date by update, see the zobrist module
+    pub(crate) hash: u64,
+    // Set by io::read_turn, update alone leaves it as it was
+    pub(crate) enemy_actions: Vec<Action>,
+}
+
+fn bool_from_i32(n: i32) -> bool {
+    n != 0
+}
+
+// Fills in the referee's flags of tiles with only their scrap, owner, units and recycler fields
+// set: can_spawn on my tiles without a recycler, can_build on those also without units,
+// in_range_of_recycler on and next to recyclers
+pub(crate) fn derive_flags(tiles: &mut [[i32; 7]], width: usize) {
+    let recyclers: Vec<Pos> = (0..tiles.len())
+        .filter(|&k| tiles[k][3] == 1)
+        .map(|k| Pos::from_index(k, width))
+        .collect();
+    for (k, tile) in tiles.iter_mut().enumerate() {
+        let pos = Pos::from_index(k, width);
+        let [scrap, owner, units, recycler, ..] = *tile;
+        let mine = owner == Owner::Me.to_code() && recycler == 0 && scrap > 0;
+        tile[4] = (mine && units == 0) as i32;
+        tile[5] = mine as i32;
+        tile[6] = (scrap > 0 && recyclers.iter().any(|&at| at.manhattan(pos) <= 1)) as i32;
+    }
+}
+
+// Multi-source BFS filling the caller's grid, reusing its queue
+pub fn bfs(sources: &[Pos], queue: &mut VecDeque<Pos>, dist: &mut Grid<u16>) {
+    let (width, height) = (dist.width(), dist.height());
+    dist.fill(UNREACHABLE);
+    queue.clear();
+    for &pos in sources {
+        dist[pos] = 0;
+        queue.push_back(pos);
+    }
+    while let Some(pos) = queue.pop_front() {
+        let next_dist = dist[pos] + 1;
+        for next in pos.neighbors(width, height) {
+            if dist[next] == UNREACHABLE {
+                dist[next] = next_dist;
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+impl GameState {
+    pub fn new(config: &GameConfig) -> Self {
+        let GameConfig { width, height } = *config;
+        let mut game = GameState {
+            turn: 0,
+            my_side: None,
+            grid: Grid::from_fn(width, height, |_| Location::default()),
+            my_matter: 0,
+            enemy_matter: 0,
+            my_robots: Vec::new(),
+            changed: Vec::new(),
+            hash: 0,
+            enemy_actions: Vec::new(),
+        };
+        game.hash = zobrist::full_hash(&game);
+        game
+    }
+
+    // 64-bit Zobrist hash of the matter counts and the tiles as the referee sent them
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // What the enemy did between the last two frames from the referee, as far as they tell
+    pub fn inferred_enemy_actions(&self) -> &[Action] {
+        &self.enemy_actions
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn config(&self) -> GameConfig {
+        GameConfig { width: self.width(), height: self.height() }
+    }
+
+    // Tiles between the tile and the closest map edge
+    pub fn dist_to_border(&self, pos: Pos) -> i32 {
+        pos.x().min(pos.y()).min(self.width() - 1 - pos.x()).min(self.height() - 1 - pos.y()) as i32
+    }
+
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        pos.neighbors(self.width(), self.height())
+    }
+
+    pub fn loc(&self, pos: Pos) -> &Location {
+        &self.grid[pos]
+    }
+
+    // Every tile in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        self.grid.positions()
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Pos, &Location)> {
+        self.grid.iter_with_pos()
+    }
+
+    pub fn iter_owned(&self, owner: Owner) -> impl Iterator<Item = (Pos, &Location)> {
+        self.iter_cells().filter(move |(_, loc)| loc.owner == owner)
+    }
+
+    pub fn iter_my_robots(&self) -> impl Iterator<Item = (Pos, &Location)> {
+        self.my_robots.iter().map(|&pos| (pos, self.loc(pos)))
+    }
+
+    // A neighbor is still there to take: not mine and not grass
+    pub fn borders_outside(&self, pos: Pos) -> bool {
+        self.neighbors(pos).any(|next| self.loc(next).owner != Owner::Me && self.loc(next).scrap_amount > 0)
+    }
+
+    // My tiles units can stand on that border a tile still there to take
+    pub fn iter_frontier(&self) -> impl Iterator<Item = (Pos, &Location)> {
+        self.iter_owned(Owner::Me).filter(|&(pos, _)| self.passable(pos) && self.borders_outside(pos))
+    }
+
+    // Applies one turn of referee input, tiles in row-major order with the 7 protocol fields.
+    // Owners are all checked first, so a bad code leaves the board as it was.
+    pub fn update(&mut self, my_matter: i32, enemy_matter: i32, tiles: &[[i32; 7]]) -> Result<(), ParseOwnerError> {
+        let owners: Vec<Owner> = tiles.iter().map(|inputs| Owner::try_from(inputs[1])).collect::<Result<_, _>>()?;
+        self.turn += 1;
+        self.hash ^= zobrist::matter_key(self.my_matter, self.enemy_matter) ^ zobrist::matter_key(my_matter, enemy_matter);
+        self.my_matter = my_matter;
+        self.enemy_matter = enemy_matter;
+        self.my_robots.clear();
+        self.changed.clear();
+        for (k, (inputs, owner)) in tiles.iter().zip(owners).enumerate() {
+            let pos = Pos::from_index(k, self.width());
+            let loc = &mut self.grid[pos];
+            let before = (loc.scrap_amount, loc.owner, loc.units, loc.recycler);
+            let old_key = zobrist::tile_key(k, loc);
+            loc.scrap_amount = inputs[0];
+            loc.owner = owner;
+            loc.units = inputs[2];
+            loc.recycler = bool_from_i32(inputs[3]);
+            loc.can_build = bool_from_i32(inputs[4]);
+            loc.can_spawn = bool_from_i32(inputs[5]);
+            loc.in_range_of_recycler = bool_from_i32(inputs[6]);
+            debug_invariant!(loc.scrap_amount >= 0, "negative scrap at {:?}", pos);
+            debug_invariant!(loc.units >= 0, "negative units at {:?}", pos);
+            debug_invariant!(loc.units == 0 || loc.scrap_amount > 0, "units on grass at {:?}", pos);
+            if before != (loc.scrap_amount, loc.owner, loc.units, loc.recycler) {
+                self.hash ^= old_key ^ zobrist::tile_key(k, loc);
+                self.changed.push(pos);
+            }
+
+            if loc.owner == Owner::Me && loc.units > 0 {
+                self.my_robots.push(pos);
+            }
+        }
+
+        if self.my_side.is_none() {
+            self.my_side = self.detect_side();
+        }
+        Ok(())
+    }
+
+    // Compares the mean column of both players' tiles, None until both are on the board
+    pub(crate) fn detect_side(&self) -> Option<Side> {
+        let mean_column = |owner: Owner| {
+            let columns: Vec<usize> = self.grid
+                .iter_with_pos()
+                .filter(|(_, loc)| loc.owner == owner)
+                .map(|(pos, _)| pos.x())
+                .collect();
+            if columns.is_empty() {
+                None
+            }
+            else {
+                Some(columns.iter().sum::<usize>() as f64 / columns.len() as f64)
+            }
+        };
+        let mine = mean_column(Owner::Me)?;
+        let theirs = mean_column(Owner::Enemy)?;
+        Some(if mine <= theirs { Side::Left } else { Side::Right })
+    }
+
+    // The board turned around its center: the right side's view as it would be from the left,
+    // and the other way around. Every position, the side and the lists of tiles follow.
+    pub fn mirrored(&self) -> GameState {
+        let (width, height) = (self.width(), self.height());
+        let mut game = self.clone();
+        game.grid = Grid::from_fn(width, height, |pos| self.loc(pos.mirrored(width, height)).clone());
+        game.my_side = self.my_side.map(Side::other);
+        game.my_robots = game.positions().filter(|&pos| game.loc(pos).owner == Owner::Me && game.loc(pos).units > 0).collect();
+        game.changed = self.changed.iter().map(|pos| pos.mirrored(width, height)).collect();
+        game.changed.sort_by_key(|pos| pos.index(width));
+        game.enemy_actions = self.enemy_actions.iter().map(|action| action.mirrored(width, height)).collect();
+        game.hash = zobrist::full_hash(&game);
+        game
+    }
+
+    // Column as seen from the left side, so heuristics can assume the enemy is to the right
+    pub fn normalized_column(&self, j: usize) -> usize {
+        match self.my_side {
+            Some(Side::Right) => self.width() - 1 - j,
+            _ => j,
+        }
+    }
+
+    // Turns before the tile becomes grass, None if it outlives every recycler harvesting it.
+    // The tile loses one scrap a turn for as long as any recycler in range stands.
+    pub fn turns_to_live(&self, pos: Pos) -> Option<i32> {
+        let scrap = self.loc(pos).scrap_amount;
+        let harvested_for = std::iter::once(pos)
+            .chain(self.neighbors(pos))
+            .filter_map(|at| self.loc(at).recycler_turns_remaining())
+            .max()?;
+        if scrap > 0 && harvested_for >= scrap { Some(scrap) } else { None }
+    }
+
+    // Units can stand on and move through the tile
+    pub fn passable(&self, pos: Pos) -> bool {
+        self.loc(pos).scrap_amount > 0 && !self.loc(pos).recycler
+    }
+
+    pub fn count_units(&self, owner: Owner) -> i32 {
+        self.iter_owned(owner).map(|(_, loc)| loc.units).sum()
+    }
+
+    // Checks one action against this turn's board as if nothing else were spent
+    pub fn validate(&self, action: &Action) -> Result<(), ActionError> {
+        self.validate_after(action, 0)
+    }
+
+    // Same, with matter already spent by the turn's earlier actions
+    pub fn validate_after(&self, action: &Action, spent: i32) -> Result<(), ActionError> {
+        let in_bounds = |pos: Pos| if self.grid.contains(pos) { Ok(()) } else { Err(ActionError::OutOfBounds(pos)) };
+        let pay = |cost: i32| {
+            if cost <= self.my_matter - spent { Ok(()) } else { Err(ActionError::NotEnoughMatter { needed: cost, available: self.my_matter - spent }) }
+        };
+        match *action {
+            Action::Move { amount, from, to } => {
+                in_bounds(from)?;
+                in_bounds(to)?;
+                let available = if self.loc(from).owner == Owner::Me { self.loc(from).units } else { 0 };
+                if amount.get() > available {
+                    return Err(ActionError::NotEnoughUnits { at: from, needed: amount.get(), available });
+                }
+                Ok(())
+            }
+            Action::Build { at } => {
+                in_bounds(at)?;
+                if !self.loc(at).can_build {
+                    return Err(ActionError::CannotBuild(at));
+                }
+                pay(10)
+            }
+            Action::Spawn { amount, at } => {
+                in_bounds(at)?;
+                if !self.loc(at).can_spawn {
+                    return Err(ActionError::CannotSpawn(at));
+                }
+                // Saturating, so an amount past i32::MAX / 10 cannot wrap around to a refund
+                pay(amount.get().saturating_mul(10))
+            }
+            Action::Wait | Action::Message { .. } => Ok(()),
+        }
+    }
+
+    // Every action that is legal on its own this turn, with amounts capped at max_amount.
+    // Moves are limited to adjacent tiles: longer MOVE targets are legal but only path one step.
+    pub fn legal_actions(&self, max_amount: i32) -> Vec<Action> {
+        let mut legal = vec![Action::Wait];
+        for (from, loc) in self.iter_my_robots() {
+            for to in self.neighbors(from).filter(|&to| self.passable(to)) {
+                for n in 1..=loc.units.min(max_amount) {
+                    let Some(amount) = Amount::new(n) else { continue };
+                    legal.push(Action::Move { amount, from, to });
+                }
+            }
+        }
+        let affordable = self.my_matter / 10;
+        for at in self.positions() {
+            if self.loc(at).can_build && affordable > 0 {
+                legal.push(Action::Build { at });
+            }
+            if self.loc(at).can_spawn {
+                for n in 1..=affordable.min(max_amount) {
+                    let Some(amount) = Amount::new(n) else { continue };
+                    legal.push(Action::Spawn { amount, at });
+                }
+            }
+        }
+        legal
+    }
+}
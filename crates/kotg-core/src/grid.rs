@@ -0,0 +1,147 @@
+use std::ops::{Index, IndexMut};
+
+use crate::pos::Pos;
+
+// One value per tile, stored row-major
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Grid { width, height, cells: vec![value; width * height] }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        Grid { width, height, cells: (0..width * height).map(|k| f(Pos::from_index(k, width))).collect() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x() < self.width && pos.y() < self.height
+    }
+
+    // The tile at column x and row y, None off the grid
+    pub fn pos(&self, x: usize, y: usize) -> Option<Pos> {
+        (x < self.width && y < self.height).then(|| Pos::new(x, y))
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) { Some(&self.cells[pos.index(self.width)]) } else { None }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) { Some(&mut self.cells[pos.index(self.width)]) } else { None }
+    }
+
+    // Every tile in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |k| Pos::from_index(k, width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn iter_with_pos(&self) -> impl Iterator<Item = (Pos, &T)> {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(k, value)| (Pos::from_index(k, width), value))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        debug_assert!(self.contains(pos), "{:?} outside a {}x{} grid", pos, self.width, self.height);
+        &self.cells[pos.index(self.width)]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        debug_assert!(self.contains(pos), "{:?} outside a {}x{} grid", pos, self.width, self.height);
+        &mut self.cells[pos.index(self.width)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(width: usize, height: usize) -> Grid<usize> {
+        Grid::from_fn(width, height, |pos| pos.index(width))
+    }
+
+    #[test]
+    fn indexes_row_major() {
+        let mut grid = numbered(4, 3);
+        let pos = grid.pos(1, 2).unwrap();
+        assert_eq!(grid[pos], 9);
+        assert_eq!(pos.row_col(), (2, 1));
+        grid[pos] = 42;
+        assert_eq!(grid.get(pos), Some(&42));
+        assert_eq!(grid.rows().nth(2), Some(&[8, 42, 10, 11][..]));
+    }
+
+    #[test]
+    fn checks_bounds() {
+        let mut grid = numbered(4, 3);
+        assert!(grid.pos(3, 2).is_some());
+        assert_eq!(grid.pos(4, 0), None);
+        assert_eq!(grid.pos(0, 3), None);
+        // A tile of a wider grid is not on this one
+        let outside = numbered(5, 3).pos(4, 0).unwrap();
+        assert!(!grid.contains(outside));
+        assert_eq!(grid.get(outside), None);
+        assert_eq!(grid.get_mut(outside), None);
+    }
+
+    #[test]
+    fn neighbors_stay_on_the_grid() {
+        let grid = numbered(4, 3);
+        let at = |x, y| grid.pos(x, y).unwrap();
+        let neighbors = |pos: Pos| pos.neighbors(grid.width(), grid.height()).collect::<Vec<_>>();
+        // In the order of Direction::ALL: right, down, left, up
+        assert_eq!(neighbors(at(1, 1)), vec![at(2, 1), at(1, 2), at(0, 1), at(1, 0)]);
+        assert_eq!(neighbors(at(0, 0)), vec![at(1, 0), at(0, 1)]);
+        assert_eq!(neighbors(at(3, 2)), vec![at(2, 2), at(3, 1)]);
+        for pos in grid.positions() {
+            assert!(neighbors(pos).into_iter().all(|neighbor| grid.contains(neighbor) && neighbor.manhattan(pos) == 1));
+        }
+    }
+
+    #[test]
+    fn iterates_row_major() {
+        let grid = numbered(3, 2);
+        let positions: Vec<(usize, usize)> = grid.positions().map(|pos| (pos.x(), pos.y())).collect();
+        assert_eq!(positions, vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+        assert!(grid.iter().copied().eq(0..6));
+        assert!(grid.iter_with_pos().all(|(pos, &k)| pos.index(grid.width()) == k && grid[pos] == k));
+    }
+}
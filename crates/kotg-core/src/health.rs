@@ -0,0 +1,92 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::action_set::Conflict;
+use crate::game::ParseOwnerError;
+use crate::io::InputError;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BotError {
+    Parse(String),
+    Invariant(String),
+    Timeout { elapsed_ms: u128, budget_ms: u128 },
+    Planner(String),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(reason) => write!(f, "parse: {reason}"),
+            Self::Invariant(reason) => write!(f, "invariant: {reason}"),
+            Self::Timeout { elapsed_ms, budget_ms } => write!(f, "timeout: {elapsed_ms}ms of {budget_ms}ms"),
+            Self::Planner(reason) => write!(f, "planner: {reason}"),
+        }
+    }
+}
+
+impl From<InputError> for BotError {
+    fn from(err: InputError) -> Self {
+        BotError::Parse(err.to_string())
+    }
+}
+
+impl From<ParseOwnerError> for BotError {
+    fn from(err: ParseOwnerError) -> Self {
+        BotError::Parse(err.to_string())
+    }
+}
+
+impl From<Conflict> for BotError {
+    fn from(conflict: Conflict) -> Self {
+        BotError::Planner(conflict.to_string())
+    }
+}
+
+// Everything that went wrong or was worked around during one turn
+#[derive(Debug, Default)]
+pub struct TurnHealth {
+    pub errors: Vec<BotError>,
+    pub fallbacks: Vec<&'static str>,
+}
+
+impl TurnHealth {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.fallbacks.is_empty()
+    }
+}
+
+impl fmt::Display for TurnHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "ok");
+        }
+        write!(f, "degraded")?;
+        for error in self.errors.iter() {
+            write!(f, ", {error}")?;
+        }
+        for fallback in self.fallbacks.iter() {
+            write!(f, ", fallback: {fallback}")?;
+        }
+        Ok(())
+    }
+}
+
+// Collected where the problem happens rather than threaded through every call,
+// the turn loop takes it once the actions are out
+thread_local! {
+    static CURRENT: RefCell<TurnHealth> = RefCell::new(TurnHealth::default());
+}
+
+pub fn report(error: BotError) {
+    CURRENT.with(|health| health.borrow_mut().errors.push(error));
+}
+
+pub fn record_fallback(what: &'static str) {
+    CURRENT.with(|health| health.borrow_mut().fallbacks.push(what));
+}
+
+// The health of the turn that just ended, the next one starts clean
+pub fn take() -> TurnHealth {
+    CURRENT.with(|health| health.take())
+}
@@ -0,0 +1,111 @@
+use crate::actions::Action;
+use crate::game::{GameState, Location, Owner};
+use crate::pos::Pos;
+
+// Instrumentation subscribes to the turn loop through these, every event is a no-op by default
+pub trait Hook {
+    fn on_turn_start(&mut self, _game: &GameState) {}
+    fn on_actions_chosen(&mut self, _game: &GameState, _actions: &[Action]) {}
+    fn on_state_diff(&mut self, _game: &GameState, _changed: &[Pos]) {}
+}
+
+// Forwards each event to every subscriber, in subscription order
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl Hooks {
+    pub fn subscribe(&mut self, hook: Box<dyn Hook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn turn_start(&mut self, game: &GameState) {
+        for hook in self.hooks.iter_mut() {
+            hook.on_turn_start(game);
+        }
+    }
+
+    pub fn actions_chosen(&mut self, game: &GameState, actions: &[Action]) {
+        for hook in self.hooks.iter_mut() {
+            hook.on_actions_chosen(game, actions);
+        }
+    }
+
+    pub fn state_diff(&mut self, game: &GameState, changed: &[Pos]) {
+        for hook in self.hooks.iter_mut() {
+            hook.on_state_diff(game, changed);
+        }
+    }
+}
+
+// Replays last turn with the simulator, my actions and the enemy's actions inferred from the
+// frames, and logs every tile where the board it predicts and the one the referee sends differ in
+// scrap, owner or units. Inference misses moves into fights, so a tile that was on or next to an
+// enemy tile can differ for good reasons; any other difference, or a matter count of mine that
+// differs, is a simulator bug.
+#[derive(Default)]
+pub struct DesyncDetector {
+    // The board my last actions were chosen on, with those actions
+    previous: Option<(GameState, Vec<Action>)>,
+}
+
+impl Hook for DesyncDetector {
+    fn on_turn_start(&mut self, game: &GameState) {
+        let Some((before, my_actions)) = self.previous.take() else { return };
+        // A skipped turn leaves a board two turns old
+        if before.turn + 1 != game.turn {
+            return;
+        }
+        let predicted = before.simulate(&my_actions, game.inferred_enemy_actions());
+        let enemy = |pos: Pos| before.loc(pos).owner == Owner::Enemy;
+        let mut unexplained = 0;
+        for (pos, loc) in game.iter_cells() {
+            let expected = predicted.loc(pos);
+            let fields = |loc: &Location| (loc.scrap_amount, loc.owner, loc.units);
+            if fields(expected) == fields(loc) {
+                continue;
+            }
+            let in_reach = enemy(pos) || before.neighbors(pos).any(enemy);
+            unexplained += !in_reach as usize;
+            eprintln!(
+                "DESYNC: turn {} {:?} scrap {}/{} owner {:?}/{:?} units {}/{}{}",
+                game.turn, pos, expected.scrap_amount, loc.scrap_amount, expected.owner, loc.owner, expected.units, loc.units,
+                if in_reach { " enemy in reach" } else { "" },
+            );
+        }
+        if predicted.my_matter != game.my_matter {
+            unexplained += 1;
+            eprintln!("DESYNC: turn {} my matter {}/{}", game.turn, predicted.my_matter, game.my_matter);
+        }
+        if unexplained > 0 {
+            eprintln!("DESYNC: turn {} has {} differences the enemy cannot explain", game.turn, unexplained);
+        }
+    }
+
+    fn on_actions_chosen(&mut self, game: &GameState, actions: &[Action]) {
+        self.previous = Some((game.clone(), actions.to_vec()));
+    }
+}
+
+// Logs every event to stderr, too verbose for the submission's truncated logs
+#[cfg(feature = "instrumentation")]
+pub struct TurnLogger;
+
+#[cfg(feature = "instrumentation")]
+impl Hook for TurnLogger {
+    fn on_turn_start(&mut self, game: &GameState) {
+        eprintln!("TURN {}: matter {}/{}", game.turn, game.my_matter, game.enemy_matter);
+    }
+
+    fn on_actions_chosen(&mut self, _game: &GameState, actions: &[Action]) {
+        eprintln!("CHOSEN: {}", crate::actions::format_actions(actions));
+    }
+
+    fn on_state_diff(&mut self, game: &GameState, changed: &[Pos]) {
+        for &pos in changed {
+            let loc = game.loc(pos);
+            eprintln!("DIFF: {:?} scrap {} {:?} units {} recycler {}", pos, loc.scrap_amount, loc.owner, loc.units, loc.recycler);
+        }
+    }
+}
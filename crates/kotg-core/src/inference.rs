@@ -0,0 +1,133 @@
+// The enemy's actions rebuilt from two consecutive referee frames. Recyclers that appeared on
+// their tiles are builds, and the matter they spent beyond those, income counted, tells how many
+// units they spawned. Each unit stayed or went one step, and spawned units stay where they were
+// spawned, so where the units went is a matching of the units before, and the spawns, to the
+// units after: a unit first stays, and takes a neighbour's place otherwise, that unit being moved
+// on in turn. When no unit died the whole turn is rebuilt this way, the next frame following from
+// it. A unit only moves one step a turn, so each MOVE targets the tile it reached, whatever the
+// enemy aimed for. Units lost in fights or on grass leave no trace and their moves are missed.
+use crate::actions::{Action, Amount};
+use crate::budget::{RECYCLER_COST, UNIT_COST};
+use crate::game::{GameState, Owner};
+use crate::grid::Grid;
+use crate::pos::Pos;
+
+// Grass tiles tried as recyclers built and gone, the subsets of more would take long
+const MAX_VANISHED: usize = 4;
+
+fn enemy_units(game: &GameState, pos: Pos) -> i32 {
+    let loc = game.loc(pos);
+    if loc.owner == Owner::Enemy { loc.units } else { 0 }
+}
+
+// Where each unit went, tried a unit at a time
+struct Matching<'a> {
+    before: &'a GameState,
+    after: &'a GameState,
+    can_spawn: Grid<bool>,
+    // Units after the turn not accounted for yet
+    room: Grid<i32>,
+    // The tile each unit of a tile ended on, and each spawn's tile
+    went: Grid<Vec<Pos>>,
+    spawned: Vec<Pos>,
+    seen: Grid<bool>,
+}
+
+impl Matching<'_> {
+    // Places one unit from the tile, or one spawn for None, moving placed units aside if needed
+    fn place(&mut self, from: Option<Pos>) -> bool {
+        let targets: Vec<Pos> = match from {
+            Some(at) => std::iter::once(at).chain(self.before.neighbors(at)).collect(),
+            None => self.before.positions().filter(|&pos| self.can_spawn[pos]).collect(),
+        };
+        for to in targets {
+            if self.seen[to] || enemy_units(self.after, to) == 0 {
+                continue;
+            }
+            self.seen[to] = true;
+            if self.room[to] > 0 {
+                self.room[to] -= 1;
+            } else {
+                // A unit placed there moves on, if it has somewhere else to go
+                let others: Vec<Option<Pos>> = std::iter::once(Some(to)).chain(self.before.neighbors(to).map(Some)).chain([None]).collect();
+                let Some(other) = others.into_iter().find(|&other| self.units(other).contains(&to) && self.place(other)) else { continue };
+                let units = self.units(other);
+                if let Some(k) = units.iter().position(|&pos| pos == to) {
+                    units.remove(k);
+                }
+            }
+            self.units(from).push(to);
+            return true;
+        }
+        false
+    }
+
+    fn units(&mut self, from: Option<Pos>) -> &mut Vec<Pos> {
+        match from {
+            Some(at) => &mut self.went[at],
+            None => &mut self.spawned,
+        }
+    }
+}
+
+// The board with recyclers on these tiles, and the matter the enemy spent on units if they built them
+fn with_builds(before: &GameState, after: &GameState, builds: &[Pos]) -> (GameState, i32) {
+    let mut with_builds = before.clone();
+    for &at in builds.iter() {
+        with_builds.grid[at].recycler = true;
+    }
+    let spent = before.enemy_matter + with_builds.income(Owner::Enemy) - after.enemy_matter - RECYCLER_COST * builds.len() as i32;
+    (with_builds, spent)
+}
+
+pub fn infer_enemy_actions(before: &GameState, after: &GameState) -> Vec<Action> {
+    let mut builds: Vec<Pos> = before
+        .iter_owned(Owner::Enemy)
+        .filter(|&(pos, loc)| !loc.recycler && after.loc(pos).recycler)
+        .map(|(pos, _)| pos)
+        .collect();
+    // A recycler built on a tile's last scrap is gone by the next frame, the tile only turns to
+    // grass. The fewest such tiles that make the matter spent add up are taken for builds.
+    let vanished: Vec<Pos> = before.iter_owned(Owner::Enemy).filter(|&(pos, loc)| !loc.recycler && loc.scrap_amount > 0 && after.loc(pos).scrap_amount == 0).map(|(pos, _)| pos).take(MAX_VANISHED).collect();
+    let mut subsets: Vec<u32> = (0..1 << vanished.len()).collect();
+    subsets.sort_by_key(|subset| subset.count_ones());
+    let chosen = subsets.into_iter().find_map(|subset| {
+        let tried: Vec<Pos> = builds.iter().copied().chain(vanished.iter().enumerate().filter(|&(k, _)| subset >> k & 1 == 1).map(|(_, &pos)| pos)).collect();
+        let (_, spent) = with_builds(before, after, &tried);
+        (spent >= 0 && spent % UNIT_COST == 0).then_some(tried)
+    });
+    builds = chosen.unwrap_or(builds);
+    let (with_builds, spent) = with_builds(before, after, &builds);
+    let n_spawns = spent.max(0) / UNIT_COST;
+
+    let (width, height) = (before.width(), before.height());
+    let mut matching = Matching {
+        before,
+        after,
+        can_spawn: Grid::from_fn(width, height, |pos| before.loc(pos).owner == Owner::Enemy && !with_builds.loc(pos).recycler),
+        room: Grid::from_fn(width, height, |pos| enemy_units(after, pos)),
+        went: Grid::new(width, height, Vec::new()),
+        spawned: Vec::new(),
+        seen: Grid::new(width, height, false),
+    };
+    let units = before.iter_owned(Owner::Enemy).flat_map(|(pos, loc)| std::iter::repeat_n(Some(pos), loc.units as usize));
+    for from in units.collect::<Vec<_>>().into_iter().chain(std::iter::repeat_n(None, n_spawns as usize)) {
+        matching.seen = Grid::new(width, height, false);
+        matching.place(from);
+    }
+
+    let mut actions: Vec<Action> = builds.iter().map(|&at| Action::Build { at }).collect();
+    for from in before.positions() {
+        for to in before.neighbors(from) {
+            if let Some(amount) = Amount::new(matching.went[from].iter().filter(|&&pos| pos == to).count() as i32) {
+                actions.push(Action::Move { amount, from, to });
+            }
+        }
+    }
+    for at in before.positions() {
+        if let Some(amount) = Amount::new(matching.spawned.iter().filter(|&&pos| pos == at).count() as i32) {
+            actions.push(Action::Spawn { amount, at });
+        }
+    }
+    actions
+}
@@ -0,0 +1,222 @@
+use std::io::BufRead;
+
+use crate::actions::{format_actions, Action};
+use crate::game::{GameConfig, GameState, Owner};
+use crate::health::{self, BotError};
+use crate::inference::infer_enemy_actions;
+
+// Far above the contest's 24x12, only there to turn away sizes whose board cannot be allocated
+const MAX_MAP_SIDE: i32 = 256;
+// Matter, scrap and units above this are line noise, and sums over a whole board stay in an i32
+const MAX_COUNT: i32 = 1 << 20;
+
+pub enum Frame {
+    Turn { my_matter: i32, enemy_matter: i32, tiles: Vec<[i32; 7]> },
+    Garbled,
+    Eof,
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Closed,
+    Malformed(String),
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "input closed"),
+            Self::Malformed(reason) => write!(f, "malformed input: {reason}"),
+        }
+    }
+}
+
+pub enum InputStatus {
+    Ready,
+    Skipped,
+    Closed,
+}
+
+pub fn parse_ints(line: &str) -> Option<Vec<i32>> {
+    line.split_whitespace().map(|field| field.parse().ok()).collect()
+}
+
+fn is_count(n: i32) -> bool {
+    (0..=MAX_COUNT).contains(&n)
+}
+
+fn is_header(fields: &[i32]) -> bool {
+    fields.len() == 2 && fields.iter().all(|&n| is_count(n))
+}
+
+// Scrap, owner, units and four 0 or 1 flags, as a real referee could send them: no units on grass
+fn is_tile(fields: &[i32]) -> bool {
+    fields.len() == 7
+        && is_count(fields[0])
+        && Owner::try_from(fields[1]).is_ok()
+        && is_count(fields[2])
+        && (fields[2] == 0 || fields[0] > 0)
+        && fields[3..].iter().all(|&flag| flag == 0 || flag == 1)
+}
+
+// Reads one turn: a 2-field header then n_tiles 7-field lines. A header showing up where a tile
+// was expected means lines were lost, so the partial turn is dropped and reading restarts from it.
+// A garbled tile line, which includes numbers no real game has, still counts toward the turn so
+// the following turns stay aligned.
+pub fn read_frame(lines: &mut impl Iterator<Item = String>, n_tiles: usize) -> Frame {
+    let mut header = loop {
+        let Some(line) = lines.next() else { return Frame::Eof };
+        match parse_ints(&line) {
+            Some(fields) if is_header(&fields) => break fields,
+            _ => health::report(BotError::Parse(format!("skipping {:?} while looking for a turn header", line))),
+        }
+    };
+    'turn: loop {
+        let mut tiles = Vec::with_capacity(n_tiles);
+        let mut garbled = false;
+        for _ in 0..n_tiles {
+            let Some(line) = lines.next() else { return Frame::Eof };
+            match parse_ints(&line) {
+                Some(fields) if is_tile(&fields) =>
+                    tiles.push([fields[0], fields[1], fields[2], fields[3], fields[4], fields[5], fields[6]]),
+                Some(fields) if is_header(&fields) => {
+                    health::report(BotError::Parse(format!("turn cut short after {} tiles", tiles.len())));
+                    header = fields;
+                    continue 'turn;
+                }
+                _ => {
+                    health::report(BotError::Parse(format!("garbled tile line {:?}", line)));
+                    garbled = true;
+                }
+            }
+        }
+        if garbled {
+            return Frame::Garbled;
+        }
+        return Frame::Turn { my_matter: header[0], enemy_matter: header[1], tiles };
+    }
+}
+
+// The map size line sent once before the first turn
+pub fn read_game(input: &mut impl BufRead) -> Result<GameConfig, InputError> {
+    let mut input_line = String::new();
+    match input.read_line(&mut input_line) {
+        Ok(0) => return Err(InputError::Closed),
+        Ok(_) => (),
+        Err(err) => return Err(InputError::Malformed(err.to_string())),
+    }
+    match parse_ints(&input_line).as_deref() {
+        Some(&[width, height]) if (1..=MAX_MAP_SIDE).contains(&width) && (1..=MAX_MAP_SIDE).contains(&height) => Ok(GameConfig { width: width as usize, height: height as usize }),
+        _ => Err(InputError::Malformed(format!("bad map size line {:?}", input_line))),
+    }
+}
+
+// A garbled turn still advances the turn counter so turn-based heuristics stay on schedule
+pub fn read_turn(input: &mut impl BufRead, game: &mut GameState) -> InputStatus {
+    let mut lines = input.lines().map_while(Result::ok);
+    match read_frame(&mut lines, game.width() * game.height()) {
+        Frame::Turn { my_matter, enemy_matter, tiles } => {
+            let previous = game.clone();
+            match game.update(my_matter, enemy_matter, &tiles) {
+                Ok(()) => {
+                    // Nothing to compare the first frame with. After a skipped frame this covers
+                    // two turns, and moves two steps long are missed.
+                    game.enemy_actions = if previous.turn > 0 { infer_enemy_actions(&previous, game) } else { Vec::new() };
+                    InputStatus::Ready
+                }
+                Err(err) => {
+                    health::report(err.into());
+                    game.turn += 1;
+                    InputStatus::Skipped
+                }
+            }
+        }
+        Frame::Garbled => {
+            game.turn += 1;
+            InputStatus::Skipped
+        }
+        Frame::Eof => InputStatus::Closed,
+    }
+}
+
+pub fn print_actions(actions: &[Action]) {
+    println!("{}", format_actions(actions));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::pos::Pos;
+
+    // Corrupted inputs for a 3x1 map, each followed by a good turn the reader must find again
+    const GOOD_TURN: &str = "10 12\n8 1 2 0 0 1 0\n8 -1 0 0 0 0 0\n8 0 3 0 0 0 0\n";
+    const CUT_SHORT: &str = "10 10\n8 1 1 0 0 1 0\n";
+    const GARBLED_TILE: &str = "10 10\n8 1 1 0 0 1 0\nMOVE 1 0 0 1 0\n8 0 1 0 0 0 0\n";
+    const IMPOSSIBLE_TILE: &str = "10 10\n8 1 1 0 0 1 0\n8 7 0 0 0 0 0\n8 0 1 0 0 0 0\n";
+    const UNITS_ON_GRASS: &str = "10 10\n8 1 1 0 0 1 0\n0 1 5 0 0 0 0\n8 0 1 0 0 0 0\n";
+    const NOISE_BEFORE_HEADER: &str = "\nhello\n1 2 3\n";
+
+    // The statuses of the turns read from `input` until it closes, and the board left
+    fn read_all(input: &str) -> (Vec<&'static str>, GameState) {
+        let mut game = GameState::new(&GameConfig { width: 3, height: 1 });
+        let mut input = Cursor::new(input);
+        let mut statuses = Vec::new();
+        loop {
+            statuses.push(match read_turn(&mut input, &mut game) {
+                InputStatus::Ready => "ready",
+                InputStatus::Skipped => "skipped",
+                InputStatus::Closed => break,
+            });
+        }
+        health::take();
+        (statuses, game)
+    }
+
+    fn assert_good_turn_read(game: &GameState) {
+        assert_eq!((game.my_matter, game.enemy_matter), (10, 12));
+        let units: Vec<i32> = game.positions().map(|pos| game.loc(pos).units).collect();
+        assert_eq!(units, vec![2, 0, 3]);
+        assert_eq!(game.my_robots, vec![Pos::new(0, 0)]);
+    }
+
+    #[test]
+    fn a_turn_cut_short_is_dropped_at_the_next_header() {
+        let (statuses, game) = read_all(&format!("{}{}", CUT_SHORT, GOOD_TURN));
+        assert_eq!(statuses, vec!["ready"]);
+        assert_good_turn_read(&game);
+    }
+
+    #[test]
+    fn a_garbled_turn_is_skipped_and_still_counted() {
+        for corrupted in [GARBLED_TILE, IMPOSSIBLE_TILE, UNITS_ON_GRASS] {
+            let (statuses, game) = read_all(&format!("{}{}", corrupted, GOOD_TURN));
+            assert_eq!(statuses, vec!["skipped", "ready"], "{:?}", corrupted);
+            assert_eq!(game.turn, 2);
+            assert_good_turn_read(&game);
+        }
+    }
+
+    #[test]
+    fn noise_before_a_header_is_skipped() {
+        let (statuses, game) = read_all(&format!("{}{}", NOISE_BEFORE_HEADER, GOOD_TURN));
+        assert_eq!(statuses, vec!["ready"]);
+        assert_good_turn_read(&game);
+    }
+
+    #[test]
+    fn input_closing_mid_turn_ends_the_game() {
+        let (statuses, game) = read_all(&format!("{}{}", GOOD_TURN, CUT_SHORT));
+        assert_eq!(statuses, vec!["ready"]);
+        assert_good_turn_read(&game);
+    }
+
+    #[test]
+    fn the_map_size_line_is_checked() {
+        for line in ["", "24\n", "0 12\n", "24 x\n", "100000 2\n"] {
+            assert!(read_game(&mut Cursor::new(line)).is_err(), "{:?} read", line);
+        }
+        assert!(matches!(read_game(&mut Cursor::new("24 12\n")), Ok(GameConfig { width: 24, height: 12 })));
+    }
+}
@@ -0,0 +1,268 @@
+// Just enough JSON for replay files, the build has no serde to lean on
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    // Keys in insertion order, which is the order they are written in
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    // The field's value, None for a missing key or a value that is not an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        let Json::Object(fields) = self else { return None };
+        fields.iter().find(|(name, _)| name == key).map(|(_, value)| value)
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        if let Json::Array(items) = self { Some(items) } else { None }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Json::String(s) = self { Some(s) } else { None }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        if let Json::Number(n) = *self { Some(n) } else { None }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseJsonError {
+    // Byte offset in the text
+    pub at: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bad JSON at byte {}: {}", self.at, self.reason)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn err(&self, reason: &str) -> ParseJsonError {
+        ParseJsonError { at: self.at, reason: reason.to_string() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseJsonError> {
+        if !self.text[self.at..].starts_with(token) {
+            return Err(self.err(&format!("expected {}", token)));
+        }
+        self.at += token.len();
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, ParseJsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.expect("true").map(|()| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|()| Json::Bool(false)),
+            Some(b'n') => self.expect("null").map(|()| Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.err("unexpected character")),
+            None => Err(self.err("unexpected end")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, ParseJsonError> {
+        self.expect("{")?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.err("expected , or }")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, ParseJsonError> {
+        self.expect("[")?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.err("expected , or ]")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseJsonError> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        let mut chars = self.text[self.at..].char_indices();
+        while let Some((k, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += k + 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            // Surrogate pairs come out as the replacement character, replays are ASCII
+                            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        _ => return Err(self.err("bad escape")),
+                    };
+                    s.push(escaped);
+                }
+                c => s.push(c),
+            }
+        }
+        Err(self.err("unterminated string"))
+    }
+
+    fn number(&mut self) -> Result<Json, ParseJsonError> {
+        let rest = &self.text[self.at..];
+        let len = rest.find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')).unwrap_or(rest.len());
+        let n = rest[..len].parse().map_err(|_| self.err("bad number"))?;
+        self.at += len;
+        Ok(Json::Number(n))
+    }
+}
+
+impl FromStr for Json {
+    type Err = ParseJsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { text: s, at: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.at < s.len() {
+            return Err(parser.err("trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Self {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+// Compact, whole numbers without a fraction
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (k, item) in items.iter().enumerate() {
+                    write!(f, "{}{}", if k > 0 { "," } else { "" }, item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (k, (key, value)) in fields.iter().enumerate() {
+                    if k > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! The bot's core as a library: the board model ([`GameConfig`], [`GameState`], [`Pos`], [`Grid`]), actions and their
+//! text protocol ([`Action`]), per-turn analyses ([`Analysis`]), and the strategy.
+//! Items re-exported here are the stable API for tools built outside this repository.
+
+// Panics in debug builds, only reported in the turn's health in the release build that gets submitted
+macro_rules! debug_invariant {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            if cfg!(debug_assertions) {
+                panic!("invariant violated: {}", format!($($arg)+));
+            }
+            else {
+                $crate::health::report($crate::health::BotError::Invariant(format!($($arg)+)));
+            }
+        }
+    };
+}
+
+pub mod action_set;
+pub mod actions;
+pub mod analysis;
+pub mod ascii;
+pub mod budget;
+mod config_default;
+pub mod game;
+pub mod grid;
+pub mod inference;
+pub mod io;
+pub mod pos;
+pub mod rng;
+pub mod search;
+pub mod sim;
+pub mod strategy;
+pub mod transposition;
+pub mod zobrist;
+
+// Used by this crate's own binaries and free to change: only documented with internal-docs
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod health;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod hooks;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod json;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod mapgen;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod referee;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod replay;
+#[cfg_attr(not(feature = "internal-docs"), doc(hidden))]
+pub mod scenario;
+
+pub use action_set::{ActionSet, Conflict};
+pub use actions::{format_actions, parse_actions, Action, Amount, ParseActionError};
+pub use analysis::Analysis;
+pub use ascii::ParseMapError;
+pub use budget::MatterBudget;
+pub use game::{ActionError, GameConfig, GameState, Location, Owner, ParseOwnerError, Side};
+pub use grid::Grid;
+pub use pos::{Direction, Pos};
+pub use rng::Rng;
+pub use sim::{ActionSets, Delta};
+pub use strategy::{Config, DirectionalPrior, GreedyOutsideStrategy, Strategy};
+pub use transposition::TranspositionTable;
@@ -0,0 +1,136 @@
+// Seeded boards shaped like the contest's, so local games look like ladder games. This is not a
+// port of the official generator: the referee's source could not be fetched where this was
+// written. The size range, the symmetry and the start come from the rules and from watching real
+// games. The scrap distribution is a guess. The mapstats binary prints these boards' statistics
+// next to those of ladder boards, so the guesses can be checked against downloaded replays:
+// - width from 12 to 24 and half as many rows
+// - the board is symmetric through its center, the enemy's half is mine turned around
+// - scrap from 0 to 10, random values smoothed with their neighbours so piles and gaps are
+//   patches rather than noise, and the lowest patches are grass from the start
+// - each player owns the 3x3 square around a start tile on their own half, with a unit on each
+//   of the start's 4 neighbours, and 10 matter
+// A board where the two starts are cut off from each other is drawn again.
+use std::collections::VecDeque;
+
+use crate::game::{derive_flags, GameConfig, GameState, Owner};
+use crate::grid::Grid;
+use crate::pos::Pos;
+use crate::rng::Rng;
+
+pub const MIN_WIDTH: usize = 12;
+pub const MAX_WIDTH: usize = 24;
+pub const MAX_SCRAP: i32 = 10;
+pub const START_MATTER: i32 = 10;
+// Smoothed scrap under this turns to grass
+const GRASS_BELOW: i32 = 3;
+const SMOOTHING_PASSES: usize = 2;
+// Boards with at least this share of grass count as broken up, about half the generated ones
+const GRASSY_SHARE: f64 = 0.15;
+
+pub struct MapGenerator;
+
+fn connected(scrap: &Grid<i32>, from: Pos, to: Pos) -> bool {
+    let mut seen = Grid::new(scrap.width(), scrap.height(), false);
+    let mut queue = VecDeque::from([from]);
+    seen[from] = true;
+    while let Some(pos) = queue.pop_front() {
+        if pos == to {
+            return true;
+        }
+        for next in pos.neighbors(scrap.width(), scrap.height()) {
+            if !seen[next] && scrap[next] > 0 {
+                seen[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    false
+}
+
+// Kinds of boards, for statistics kept by kind of map: the width band and whether grass breaks
+// the board up
+pub const ARCHETYPES: [&str; 6] = ["small-open", "small-grassy", "medium-open", "medium-grassy", "large-open", "large-grassy"];
+
+pub fn archetype(game: &GameState) -> &'static str {
+    let n_grass = game.positions().filter(|&pos| game.loc(pos).scrap_amount == 0).count();
+    let grassy = n_grass as f64 >= GRASSY_SHARE * (game.width() * game.height()) as f64;
+    let band = match game.width() {
+        ..=15 => 0,
+        16..=19 => 1,
+        _ => 2,
+    };
+    ARCHETYPES[2 * band + grassy as usize]
+}
+
+impl MapGenerator {
+    // The first turn of the game the seed picks, seen by the player on the left
+    pub fn generate(seed: u64) -> GameState {
+        let mut rng = Rng::new(seed);
+        let width = rng.gen_range(MIN_WIDTH as i32..MAX_WIDTH as i32 + 1) as usize;
+        let height = width / 2;
+        loop {
+            let (scrap, start) = Self::draw(&mut rng, width, height);
+            if connected(&scrap, start, start.mirrored(width, height)) {
+                return Self::first_turn(&scrap, start);
+            }
+        }
+    }
+
+    // Scrap on every tile and my start
+    fn draw(rng: &mut Rng, width: usize, height: usize) -> (Grid<i32>, Pos) {
+        let mut noise = Grid::new(width, height, 0.0);
+        for pos in noise.positions() {
+            let value = rng.gen_range(0..MAX_SCRAP + 1) as f64;
+            // Drawing each pair once keeps the board symmetric
+            if pos.index(width) <= pos.mirrored(width, height).index(width) {
+                noise[pos] = value;
+                noise[pos.mirrored(width, height)] = value;
+            }
+        }
+        for _ in 0..SMOOTHING_PASSES {
+            noise = Grid::from_fn(width, height, |pos| {
+                let square: Vec<f64> = (pos.y().saturating_sub(1)..(pos.y() + 2).min(height))
+                    .flat_map(|y| (pos.x().saturating_sub(1)..(pos.x() + 2).min(width)).map(move |x| Pos::new(x, y)))
+                    .map(|at| noise[at])
+                    .collect();
+                square.iter().sum::<f64>() / square.len() as f64
+            });
+        }
+        // Smoothing pulls everything toward the middle, spread the values back over the whole range
+        let (low, high) = noise.iter().fold((f64::MAX, f64::MIN), |(low, high), &value| (low.min(value), high.max(value)));
+        let mut scrap = Grid::from_fn(width, height, |pos| {
+            // A tile's mean and its mirror's can round apart, both take the first one's
+            let pos = if pos.index(width) <= pos.mirrored(width, height).index(width) { pos } else { pos.mirrored(width, height) };
+            let stretched = if high > low { ((noise[pos] - low) * MAX_SCRAP as f64 / (high - low)).round() as i32 } else { MAX_SCRAP / 2 };
+            if stretched < GRASS_BELOW { 0 } else { stretched }
+        });
+        let start = Pos::new(rng.gen_range(1..width as i32 / 2 - 1) as usize, rng.gen_range(1..height as i32 - 1) as usize);
+        for pos in [start, start.mirrored(width, height)] {
+            for y in pos.y() - 1..=pos.y() + 1 {
+                for x in pos.x() - 1..=pos.x() + 1 {
+                    scrap[Pos::new(x, y)] = scrap[Pos::new(x, y)].max(1);
+                }
+            }
+        }
+        (scrap, start)
+    }
+
+    fn first_turn(scrap: &Grid<i32>, start: Pos) -> GameState {
+        let (width, height) = (scrap.width(), scrap.height());
+        let mut tiles: Vec<[i32; 7]> = scrap.iter().map(|&value| [value, Owner::Neutral.to_code(), 0, 0, 0, 0, 0]).collect();
+        for (owner, center) in [(Owner::Me, start), (Owner::Enemy, start.mirrored(width, height))] {
+            for y in center.y() - 1..=center.y() + 1 {
+                for x in center.x() - 1..=center.x() + 1 {
+                    let tile = &mut tiles[Pos::new(x, y).index(width)];
+                    tile[1] = owner.to_code();
+                    tile[2] = (center.manhattan(Pos::new(x, y)) == 1) as i32;
+                }
+            }
+        }
+        derive_flags(&mut tiles, width);
+        let mut game = GameState::new(&GameConfig { width, height });
+        let result = game.update(START_MATTER, START_MATTER, &tiles);
+        debug_invariant!(result.is_ok(), "generated tiles rejected: {:?}", result);
+        game
+    }
+}
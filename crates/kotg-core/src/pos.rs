@@ -0,0 +1,91 @@
+use std::fmt;
+
+// Engine coordinates: x is the column, y the row. Outside the crate a Pos only comes from a
+// board, Grid::pos checks the coordinates, so every Pos a caller holds is on some map.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pos {
+    x: usize,
+    y: usize,
+}
+
+impl Pos {
+    // Unchecked, for the parser and for tiles derived from ones already on the board
+    pub(crate) fn new(x: usize, y: usize) -> Self {
+        Pos { x, y }
+    }
+
+    pub fn x(self) -> usize {
+        self.x
+    }
+
+    pub fn y(self) -> usize {
+        self.y
+    }
+
+    pub fn row_col(self) -> (usize, usize) {
+        (self.y, self.x)
+    }
+
+    // Row-major index into flat per-tile buffers
+    pub fn index(self, width: usize) -> usize {
+        self.y * width + self.x
+    }
+
+    pub(crate) fn from_index(k: usize, width: usize) -> Self {
+        Pos { x: k % width, y: k / width }
+    }
+
+    // The tile turned around the board's center, as the starts are
+    pub fn mirrored(self, width: usize, height: usize) -> Pos {
+        Pos::new(width - 1 - self.x, height - 1 - self.y)
+    }
+
+    pub fn manhattan(self, other: Pos) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    // On-map neighbors, in the order of Direction::ALL
+    pub fn neighbors(self, width: usize, height: usize) -> impl Iterator<Item = Pos> {
+        Direction::ALL.into_iter().filter_map(move |direction| direction.step(self, width, height))
+    }
+
+    // The direction of an adjacent tile, None if the tiles are not adjacent
+    pub fn direction_to(self, other: Pos) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|direction| direction.step(self, usize::MAX, usize::MAX) == Some(other))
+    }
+}
+
+// Up is toward row 0, as in the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Right, Direction::Down, Direction::Left, Direction::Up];
+
+    // The adjacent tile that way, None past the map edge
+    pub fn step(self, pos: Pos, width: usize, height: usize) -> Option<Pos> {
+        let Pos { x, y } = pos;
+        match self {
+            Direction::Up => (y > 0).then(|| Pos::new(x, y - 1)),
+            Direction::Down => (y + 1 < height).then(|| Pos::new(x, y + 1)),
+            Direction::Left => (x > 0).then(|| Pos::new(x - 1, y)),
+            Direction::Right => (x + 1 < width).then(|| Pos::new(x + 1, y)),
+        }
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Direction::Left | Direction::Right)
+    }
+}
+
+// Short form for the debug logs, which CodinGame truncates
+impl fmt::Debug for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
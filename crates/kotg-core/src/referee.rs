@@ -0,0 +1,600 @@
+// Whole games played locally under the official rules, for self-play, tuning and regression
+// runs. Each bot reads the same text the CodinGame referee sends, the map size before its first
+// turn then a frame every turn, and answers with one line of actions. Output that does not parse
+// disqualifies the bot, and the game is a draw when both are on the same turn. Actions that parse
+// but are illegal are skipped, as on the real referee.
+// The simulator plays the turn on player 0's view of the board, player 1 sees it with the owners
+// swapped. The game ends after MAX_TURNS turns, once a player has no tile left, or once
+// STALL_TURNS turns in a row changed no tile's owner or scrap. The player owning more tiles wins.
+use std::fmt::Write;
+use std::io::{BufRead, BufReader, Cursor, Write as _};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::action_set::ActionSet;
+use crate::actions::{format_actions, parse_actions, Action};
+use crate::budget::{RECYCLER_COST, UNIT_COST};
+use crate::game::{derive_flags, GameState, Owner};
+use crate::grid::Grid;
+use crate::health;
+use crate::io::{self, InputStatus};
+use crate::mapgen::MapGenerator;
+use crate::replay::Replay;
+use crate::sim::protocol_fields;
+use crate::strategy::{Config, DirectionalPrior, GreedyOutsideStrategy, Strategy};
+
+pub const MAX_TURNS: usize = 200;
+pub const STALL_TURNS: usize = 20;
+// When kotg-core was last built, which changes with its code: results saved by an older build
+// may not hold any more
+pub const CORE_BUILD: &str = match option_env!("BUILD_TIMESTAMP") {
+    Some(timestamp) => timestamp,
+    None => "unknown",
+};
+
+// The seed of the player's bot in a self-play game, so both sides get their own stream and a
+// replay of the game can seed the bot again from the game's seed
+pub fn bot_seed(game_seed: u64, player: usize) -> u64 {
+    game_seed.wrapping_mul(2).wrapping_add(player as u64)
+}
+
+pub trait Bot {
+    // The lines the bot reads this turn, each ending with a newline, to its line of actions
+    fn play(&mut self, input: &str) -> String;
+}
+
+impl<F: FnMut(&str) -> String> Bot for F {
+    fn play(&mut self, input: &str) -> String {
+        self(input)
+    }
+}
+
+// Turns of the opponent's opening its moves are noted for
+const OPENING_TURNS: usize = 5;
+
+// What a bot learned about one opponent over the games of a series: the defense margin it ended
+// each game on, the turn the opponent built its first recycler, and how many of the moves it saw
+// in the opponent's opening came toward it across columns. The moves are those the bot infers
+// from the boards, so moves into fights are missed. Only the margin feeds the strategy, which
+// starts the next game on its mean; the rest are notes to read.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OpponentNotes {
+    pub games: u32,
+    pub margin_total: i32,
+    // Games the opponent built a recycler in, and the turns it first did
+    pub build_games: u32,
+    pub first_build_total: u32,
+    pub opening_moves: u32,
+    pub opening_forward: u32,
+}
+
+impl OpponentNotes {
+    // The margin to start the next game on, None before the first game
+    pub fn margin(&self) -> Option<i32> {
+        (self.games > 0).then(|| (self.margin_total as f64 / self.games as f64).round() as i32)
+    }
+
+    pub fn first_build_turn(&self) -> Option<f64> {
+        (self.build_games > 0).then(|| self.first_build_total as f64 / self.build_games as f64)
+    }
+
+    pub fn opening_forward_share(&self) -> Option<f64> {
+        (self.opening_moves > 0).then(|| self.opening_forward as f64 / self.opening_moves as f64)
+    }
+
+    pub fn merged(self, other: OpponentNotes) -> OpponentNotes {
+        OpponentNotes {
+            games: self.games + other.games,
+            margin_total: self.margin_total + other.margin_total,
+            build_games: self.build_games + other.build_games,
+            first_build_total: self.first_build_total + other.first_build_total,
+            opening_moves: self.opening_moves + other.opening_moves,
+            opening_forward: self.opening_forward + other.opening_forward,
+        }
+    }
+
+    // What the opponent's inferred actions on this turn tell
+    fn observe(&mut self, game: &GameState) {
+        let enemy_actions = game.inferred_enemy_actions();
+        if self.build_games == 0 && enemy_actions.iter().any(|action| matches!(action, Action::Build { .. })) {
+            self.build_games = 1;
+            self.first_build_total = game.turn as u32;
+        }
+        if game.turn <= OPENING_TURNS {
+            for action in enemy_actions {
+                if let Action::Move { from, to, .. } = *action {
+                    self.opening_moves += 1;
+                    self.opening_forward += (game.normalized_column(to.x()) < game.normalized_column(from.x())) as u32;
+                }
+            }
+        }
+    }
+}
+
+// The greedy strategy playing in process, reading its input the way the bot's main loop does.
+// Like the main loop it takes the turn's health once the actions are out, so the thread's buffer
+// stays one turn long; the referee's own reports on this thread land in it too.
+pub struct StrategyBot {
+    config: Config,
+    playing: Option<(GameState, GreedyOutsideStrategy)>,
+    degraded_turns: usize,
+    // What earlier games taught about the opponent, and what this one does
+    opponent: OpponentNotes,
+    noted: OpponentNotes,
+}
+
+impl StrategyBot {
+    pub fn new(config: Config) -> Self {
+        StrategyBot::against(config, OpponentNotes::default())
+    }
+
+    // A bot starting from what earlier games against its opponent taught
+    pub fn against(config: Config, opponent: OpponentNotes) -> Self {
+        StrategyBot { config, playing: None, degraded_turns: 0, opponent, noted: OpponentNotes::default() }
+    }
+
+    // What this game taught about the opponent so far, as one game
+    pub fn notes(&self) -> OpponentNotes {
+        let margin = self.playing.as_ref().map_or(0, |(_, strategy)| strategy.defense_margin());
+        OpponentNotes { games: 1, margin_total: margin, ..self.noted }
+    }
+
+    // Turns that reported an error or fell back on something, as the bot's HEALTH lines count them
+    pub fn degraded_turns(&self) -> usize {
+        self.degraded_turns
+    }
+
+    fn turn_done(&mut self) {
+        if !health::take().is_ok() {
+            self.degraded_turns += 1;
+        }
+    }
+}
+
+impl Bot for StrategyBot {
+    fn play(&mut self, input: &str) -> String {
+        let mut input = Cursor::new(input);
+        if self.playing.is_none() {
+            let Ok(game_config) = io::read_game(&mut input) else { return String::new() };
+            let game = GameState::new(&game_config);
+            let mut strategy = GreedyOutsideStrategy::new(&game, self.config);
+            if let Some(margin) = self.opponent.margin() {
+                strategy.seed_defense_margin(margin);
+            }
+            self.playing = Some((game, strategy));
+        }
+        let Some((game, strategy)) = self.playing.as_mut() else { return String::new() };
+        if !matches!(io::read_turn(&mut input, game), InputStatus::Ready) {
+            health::record_fallback("WAIT on garbled turn");
+            self.turn_done();
+            return Action::Wait.to_string();
+        }
+        self.noted.observe(game);
+        let mut action_set = ActionSet::new(strategy.plan(game));
+        for conflict in action_set.resolve(game) {
+            health::report(conflict.into());
+        }
+        let line = format_actions(&action_set.into_actions());
+        self.turn_done();
+        line
+    }
+}
+
+// Named Configs the tools pit against each other: the default and variations of it one or two
+// parameters away
+pub fn presets() -> Vec<(&'static str, Config)> {
+    let default = Config::default();
+    let prior = |toward_enemy, toward_center, along_rows| DirectionalPrior { toward_enemy, toward_center, along_rows };
+    vec![
+        ("default", default),
+        ("untuned", Config { defense_margin_range: (0, 3), ..default }),
+        ("cautious", Config { defense_margin_range: (3, 6), ..default }),
+        ("center", Config { expansion_prior: prior(0.5, 1., 0.), ..default }),
+        ("rows", Config { expansion_prior: prior(1., 0., 1.), ..default }),
+        ("explore", Config { exploration: 0.1, ..default }),
+    ]
+}
+
+pub fn preset(name: &str) -> Option<Config> {
+    presets().into_iter().find(|&(preset, _)| preset == name).map(|(_, config)| config)
+}
+
+// What both Configs scored over a duel, indexed like them: a point for a game won and half of one
+// for a draw, the same for each region at the end of each game
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Duel {
+    pub points: [f64; 2],
+    pub region_points: [[f64; 2]; REGIONS.len()],
+}
+
+// Both Configs play the generated map of the seed from each side, their bots seeded as in the
+// arena
+pub fn duel(configs: [Config; 2], seed: u64) -> Duel {
+    play_duel(configs, seed, None)
+}
+
+// The same, each Config's bot starting from its notes on the other, which both games add to
+pub fn duel_with_notes(configs: [Config; 2], seed: u64, notes: &mut [OpponentNotes; 2]) -> Duel {
+    play_duel(configs, seed, Some(notes))
+}
+
+fn play_duel(configs: [Config; 2], seed: u64, mut notes: Option<&mut [OpponentNotes; 2]>) -> Duel {
+    let start = MapGenerator::generate(seed);
+    let mut duel = Duel::default();
+    for first in 0..2 {
+        let mut bots = [0, 1].map(|player| {
+            let config = Config { seed: Some(bot_seed(seed, player)), ..configs[first ^ player] };
+            notes.as_ref().map_or_else(|| StrategyBot::new(config), |notes| StrategyBot::against(config, notes[first ^ player]))
+        });
+        let [left, right] = &mut bots;
+        let result = Referee::new(&start).run([left, right]);
+        if let Some(notes) = notes.as_deref_mut() {
+            for (player, bot) in bots.iter().enumerate() {
+                notes[first ^ player] = notes[first ^ player].merged(bot.notes());
+            }
+        }
+        let add = |points: &mut [f64; 2], winner: Option<usize>| {
+            for (player, won) in points_by_player(winner).into_iter().enumerate() {
+                points[first ^ player] += won;
+            }
+        };
+        add(&mut duel.points, result.winner());
+        for (region, scores) in result.region_scores().into_iter().enumerate() {
+            add(&mut duel.region_points[region], leader(scores));
+        }
+    }
+    duel
+}
+
+// A bot in its own process, started from a shell command line. Its stderr goes to ours.
+pub struct ProcessBot {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ProcessBot {
+    pub fn spawn(command: &str) -> std::io::Result<Self> {
+        let mut child = Command::new("sh").arg("-c").arg(command).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(std::io::Error::other("no pipe to the bot"));
+        };
+        Ok(ProcessBot { child, stdin, stdout: BufReader::new(stdout) })
+    }
+}
+
+impl Bot for ProcessBot {
+    // A bot that went away answers with the reason, which does not parse and disqualifies it
+    fn play(&mut self, input: &str) -> String {
+        if let Err(err) = self.stdin.write_all(input.as_bytes()).and_then(|()| self.stdin.flush()) {
+            return format!("cannot write to the bot: {}", err);
+        }
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0) => "the bot closed its output".to_string(),
+            Ok(_) => line.trim_end().to_string(),
+            Err(err) => format!("cannot read from the bot: {}", err),
+        }
+    }
+}
+
+impl Drop for ProcessBot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    MaxTurns,
+    NoTiles,
+    Stalled,
+    // The player whose output did not parse
+    Disqualified(usize),
+    // Neither output parsed on the same turn
+    BothDisqualified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub end: End,
+    pub turns: usize,
+    // Tiles owned by each player on the last board
+    pub scores: [usize; 2],
+    // The tiles that made the scores, see audit
+    pub owners: Grid<Option<usize>>,
+}
+
+// Bands of rows the end of a game is broken down by, north to south
+pub const REGIONS: [&str; 3] = ["north", "middle", "south"];
+
+// The player with more tiles, None when even
+fn leader(scores: [usize; 2]) -> Option<usize> {
+    (scores[0] != scores[1]).then_some(if scores[0] > scores[1] { 0 } else { 1 })
+}
+
+fn points_by_player(winner: Option<usize>) -> [f64; 2] {
+    match winner {
+        Some(0) => [1., 0.],
+        Some(_) => [0., 1.],
+        None => [0.5, 0.5],
+    }
+}
+
+impl GameResult {
+    // None on a draw
+    pub fn winner(&self) -> Option<usize> {
+        match self.end {
+            End::Disqualified(player) => Some(1 - player),
+            End::BothDisqualified => None,
+            _ => leader(self.scores),
+        }
+    }
+
+    // The tiles each player owns at the end in each of the REGIONS
+    pub fn region_scores(&self) -> [[usize; 2]; REGIONS.len()] {
+        let mut scores = [[0; 2]; REGIONS.len()];
+        for (pos, &owner) in self.owners.iter_with_pos() {
+            if let Some(player) = owner {
+                scores[pos.y() * REGIONS.len() / self.owners.height()][player] += 1;
+            }
+        }
+        scores
+    }
+
+    // Who holds more of each region at the end, None when even
+    pub fn region_leaders(&self) -> [Option<usize>; REGIONS.len()] {
+        self.region_scores().map(leader)
+    }
+}
+
+// The player each tile of player 0's board scores for, as CodinGame counts them at the end: an
+// owned tile counts for its owner, recycler or not, neutral tiles and grass for nobody. The player
+// with more tiles wins and equal counts are a draw, CodinGame breaks no tie.
+pub fn audit(game: &GameState) -> Grid<Option<usize>> {
+    Grid::from_fn(game.width(), game.height(), |pos| match game.loc(pos).owner {
+        Owner::Me => Some(0),
+        Owner::Enemy => Some(1),
+        Owner::Neutral => None,
+    })
+}
+
+fn count_scores(owners: &Grid<Option<usize>>) -> [usize; 2] {
+    [0, 1].map(|player| owners.iter().filter(|&&owner| owner == Some(player)).count())
+}
+
+// What the referee told each player about their actions this turn
+#[derive(Debug, Default)]
+pub struct TurnReport {
+    pub rejected: [Vec<String>; 2],
+}
+
+pub struct Referee {
+    // Each player's view of the board, Me being that player
+    views: [GameState; 2],
+    turns: usize,
+    // The turns played before this referee took over, 0 unless resumed
+    first_turn: usize,
+    stalled: usize,
+    end: Option<End>,
+}
+
+fn cost(action: &Action) -> i32 {
+    match *action {
+        Action::Build { .. } => RECYCLER_COST,
+        Action::Spawn { amount, .. } => UNIT_COST * amount.get(),
+        _ => 0,
+    }
+}
+
+// The protocol fields the player gets for player 0's board. The flags are derived again rather
+// than copied, so a start board edited by hand cannot carry stale ones.
+pub(crate) fn tiles_seen_by(game: &GameState, player: usize) -> Vec<[i32; 7]> {
+    let mut tiles: Vec<[i32; 7]> = game.grid
+        .iter()
+        .map(|loc| {
+            let owner = match (loc.owner, player) {
+                (Owner::Me, 1) => Owner::Enemy,
+                (Owner::Enemy, 1) => Owner::Me,
+                (owner, _) => owner,
+            };
+            [loc.scrap_amount, owner.to_code(), loc.units, loc.recycler as i32, 0, 0, 0]
+        })
+        .collect();
+    derive_flags(&mut tiles, game.width());
+    tiles
+}
+
+// The board `player` sees given player 0's, on the same turn. What each player saw the other do
+// is not known, so there are no inferred actions.
+pub fn board_seen_by(game: &GameState, player: usize) -> GameState {
+    let matter = if player == 0 { (game.my_matter, game.enemy_matter) } else { (game.enemy_matter, game.my_matter) };
+    let mut board = GameState::new(&game.config());
+    let result = board.update(matter.0, matter.1, &tiles_seen_by(game, player));
+    debug_invariant!(result.is_ok(), "board seen by {} rejected: {:?}", player, result);
+    board.turn = game.turn;
+    board.my_side = game.my_side.map(|side| if player == 0 { side } else { side.other() });
+    board.changed = game.changed.clone();
+    board
+}
+
+impl Referee {
+    // A game starting on `start`, seen by player 0
+    pub fn new(start: &GameState) -> Self {
+        Referee::resume(start, 1)
+    }
+
+    // A game going on from `board`, seen by player 0, on the given turn. The turns before count
+    // towards MAX_TURNS, the stall count starts again. The bots start with the board, their first
+    // input carries the map size and they know nothing of the turns before.
+    pub fn resume(board: &GameState, turn: usize) -> Self {
+        let mut views = [GameState::new(&board.config()), GameState::new(&board.config())];
+        let results = [
+            views[0].update(board.my_matter, board.enemy_matter, &tiles_seen_by(board, 0)),
+            views[1].update(board.enemy_matter, board.my_matter, &tiles_seen_by(board, 1)),
+        ];
+        debug_invariant!(results.iter().all(Result::is_ok), "start board rejected: {:?}", results);
+        let first_turn = turn.max(1) - 1;
+        for view in views.iter_mut() {
+            view.turn = first_turn + 1;
+        }
+        let mut referee = Referee { views, turns: first_turn, first_turn, stalled: 0, end: None };
+        referee.check_end();
+        referee
+    }
+
+    pub fn view(&self, player: usize) -> &GameState {
+        &self.views[player]
+    }
+
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.end.is_some()
+    }
+
+    pub fn scores(&self) -> [usize; 2] {
+        count_scores(&audit(&self.views[0]))
+    }
+
+    // None while the game goes on
+    pub fn result(&self) -> Option<GameResult> {
+        let end = self.end?;
+        let owners = audit(&self.views[0]);
+        Some(GameResult { end, turns: self.turns, scores: count_scores(&owners), owners })
+    }
+
+    // What the player reads this turn, the map size line first on the first turn
+    pub fn input(&self, player: usize) -> String {
+        let game = &self.views[player];
+        let mut input = String::new();
+        if self.turns == self.first_turn {
+            let _ = writeln!(input, "{} {}", game.width(), game.height());
+        }
+        let _ = writeln!(input, "{} {}", game.my_matter, game.enemy_matter);
+        for loc in game.grid.iter() {
+            let fields = protocol_fields(loc).map(|field| field.to_string());
+            let _ = writeln!(input, "{}", fields.join(" "));
+        }
+        input
+    }
+
+    // Plays one turn from both players' output lines
+    pub fn step(&mut self, outputs: [&str; 2]) -> TurnReport {
+        let mut report = TurnReport::default();
+        if self.is_over() {
+            return report;
+        }
+        let mut actions: [Vec<Action>; 2] = Default::default();
+        let mut disqualified = [false; 2];
+        for player in 0..2 {
+            let parsed = match parse_actions(outputs[player]) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    report.rejected[player].push(err.to_string());
+                    disqualified[player] = true;
+                    continue;
+                }
+            };
+            let mut spent = 0;
+            for action in parsed {
+                match self.views[player].validate_after(&action, spent) {
+                    Ok(()) => {
+                        spent += cost(&action);
+                        actions[player].push(action);
+                    }
+                    Err(err) => report.rejected[player].push(format!("{}: {}", action, err)),
+                }
+            }
+        }
+        self.end = match disqualified {
+            [true, true] => Some(End::BothDisqualified),
+            [true, false] => Some(End::Disqualified(0)),
+            [false, true] => Some(End::Disqualified(1)),
+            [false, false] => None,
+        };
+        if self.is_over() {
+            return report;
+        }
+
+        let before: Vec<(i32, Owner)> = self.views[0].grid.iter().map(|loc| (loc.scrap_amount, loc.owner)).collect();
+        let next = self.views[0].simulate(&actions[0], &actions[1]);
+        let result = self.views[1].update(next.enemy_matter, next.my_matter, &tiles_seen_by(&next, 1));
+        debug_invariant!(result.is_ok(), "swapped board rejected: {:?}", result);
+        let changed = next.grid.iter().zip(before).any(|(loc, before)| (loc.scrap_amount, loc.owner) != before);
+        self.views[0] = next;
+        self.turns += 1;
+        self.stalled = if changed { 0 } else { self.stalled + 1 };
+        self.check_end();
+        report
+    }
+
+    fn check_end(&mut self) {
+        if self.end.is_some() {
+            return;
+        }
+        self.end = if self.scores().contains(&0) {
+            Some(End::NoTiles)
+        }
+        else if self.stalled >= STALL_TURNS {
+            Some(End::Stalled)
+        }
+        else if self.turns >= MAX_TURNS {
+            Some(End::MaxTurns)
+        }
+        else {
+            None
+        };
+    }
+
+    // Plays the game out, bots[0] as player 0
+    pub fn run(self, bots: [&mut dyn Bot; 2]) -> GameResult {
+        self.play_out(bots, None)
+    }
+
+    // Same, with every turn written to the replay
+    pub fn run_recorded(self, bots: [&mut dyn Bot; 2], replay: &mut Replay) -> GameResult {
+        self.play_out(bots, Some(replay))
+    }
+
+    fn play_out(mut self, bots: [&mut dyn Bot; 2], mut replay: Option<&mut Replay>) -> GameResult {
+        let [first, second] = bots;
+        loop {
+            if let Some(result) = self.result() {
+                if let Some(replay) = replay {
+                    replay.record_end(&self.views[0], &result);
+                }
+                return result;
+            }
+            let inputs = [self.input(0), self.input(1)];
+            let outputs = [first.play(&inputs[0]), second.play(&inputs[1])];
+            let before = replay.is_some().then(|| self.views[0].clone());
+            let report = self.step([outputs[0].as_str(), outputs[1].as_str()]);
+            if let (Some(replay), Some(before)) = (replay.as_deref_mut(), before) {
+                replay.record_turn(&before, &inputs, &outputs, &report);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_carry_over_a_series_and_seed_the_margin() {
+        let configs = [preset("cautious").unwrap(), preset("untuned").unwrap()];
+        let mut notes = [OpponentNotes::default(); 2];
+        duel_with_notes(configs, 0, &mut notes);
+        assert!(notes.iter().all(|noted| noted.games == 2 && noted.opening_moves > 0));
+
+        let input = Referee::new(&MapGenerator::generate(0)).input(0);
+        for (margin, started_on) in [(5, 5), (9, 6), (-1, 3)] {
+            let mut bot = StrategyBot::against(configs[0], OpponentNotes { games: 1, margin_total: margin, ..OpponentNotes::default() });
+            bot.play(&input);
+            assert_eq!(bot.notes().margin_total, started_on);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+// Locally played games written as replay files, to watch them rather than read stderr dumps.
+// The file follows CodinGame's replay layout, a gameResult with agents, frames, scores and ranks,
+// with one frame per turn. A frame holds the board the turn was played on, owners given as
+// player indexes and -1 for neutral, and for each player the input they read, the line they
+// answered and the actions the referee skipped. A last frame holds the final board.
+//
+//   {"gameResult":{"seed":"7","end":"Stalled","turns":45,"agents":[{"index":0,"name":"greedy"},...],
+//    "frames":[{"keyframe":true,"turn":1,"matter":[10,10],"grid":[[[5,-1,0,0],...],...],
+//               "players":[{"input":"...","stdout":"...","rejected":[]},...]},...],
+//    "scores":[25,34],"ranks":[1,0]}}
+//
+// golden/replay-sample.json is a whole short game in this layout, kept in step with the writer by
+// a test here (BLESS=1 writes it again). The layout is this crate's own: no viewer's schema was
+// at hand to check it against, so a viewer that wants CodinGame's own frames, with their view
+// field, does not load these files.
+//
+// Replays downloaded from CodinGame follow the same layout, but a frame there is one agent's turn:
+// its agentId, the line it answered as stdout and its stderr, next to what the viewer draws. The
+// input the referee sent is not in them. When one of the agents is this bot, the BOARD line it
+// writes to stderr on its first turn gives the starting board, and every turn's input is rebuilt
+// by playing both agents' lines on it with the local referee. Games of two other players cannot
+// be played again.
+//
+//   {"gameResult":{"frames":[{"keyframe":true,"view":"..."},
+//                            {"agentId":0,"stdout":"MOVE 1 2 3 3 3\n","stderr":"BOARD: 10 10 / ...\n..."},
+//                            {"agentId":1,"stdout":"WAIT\n","stderr":""},...],...}}
+use crate::ascii::parse_map;
+use crate::game::{derive_flags, GameConfig, GameState, Owner};
+use crate::io::parse_ints;
+use crate::json::Json;
+use crate::referee::{tiles_seen_by, GameResult, Referee, TurnReport};
+
+const BOARD_PREFIX: &str = "BOARD: ";
+const SEED_PREFIX: &str = "SEED: ";
+
+// One player's turn read back from a replay file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedTurn {
+    pub turn: i32,
+    pub input: String,
+    pub stdout: String,
+}
+
+pub struct Replay {
+    pub seed: Option<u64>,
+    names: [String; 2],
+    frames: Vec<Json>,
+    result: Option<GameResult>,
+}
+
+fn board(game: &GameState) -> Vec<(&'static str, Json)> {
+    let owner = |owner: Owner| match owner {
+        Owner::Me => 0,
+        Owner::Enemy => 1,
+        Owner::Neutral => -1,
+    };
+    let grid = game.grid
+        .rows()
+        .map(|row| Json::Array(row.iter().map(|loc| vec![loc.scrap_amount, owner(loc.owner), loc.units, loc.recycler as i32].into()).collect()))
+        .collect();
+    vec![
+        ("keyframe", Json::Bool(true)),
+        ("turn", game.turn.into()),
+        ("matter", vec![game.my_matter, game.enemy_matter].into()),
+        ("grid", Json::Array(grid)),
+    ]
+}
+
+// Each agent's stdout and stderr, one pair per turn it played
+type AgentOutputs = [Vec<(String, String)>; 2];
+
+// The agents' outputs in CodinGame's frames
+fn codingame_outputs(frames: &[Json]) -> AgentOutputs {
+    let mut outputs: AgentOutputs = Default::default();
+    for frame in frames {
+        let agent = frame.get("agentId").and_then(Json::as_f64).filter(|&agent| agent == 0. || agent == 1.);
+        let (Some(agent), Some(stdout)) = (agent, frame.get("stdout").and_then(Json::as_str)) else {
+            continue;
+        };
+        let stderr = frame.get("stderr").and_then(Json::as_str).unwrap_or_default();
+        outputs[agent as usize].push((stdout.to_string(), stderr.to_string()));
+    }
+    outputs
+}
+
+// The board of a BOARD line, seen by the agent that wrote it
+fn parse_board(line: &str) -> Result<GameState, String> {
+    let mut parts = line.split(" / ");
+    let Some([my_matter, enemy_matter]) = parts.next().and_then(parse_ints).and_then(|matter| <[i32; 2]>::try_from(matter).ok()) else {
+        return Err("the BOARD line does not start with both matter counts".to_string());
+    };
+    let map = parse_map(parts).map_err(|err| format!("the BOARD line does not read: {}", err))?;
+    let mut board = GameState::new(&GameConfig { width: map.width, height: map.height });
+    board.update(my_matter, enemy_matter, &map.tiles).map_err(|err| format!("the BOARD line does not read: {:?}", err))?;
+    Ok(board)
+}
+
+// The agent that wrote the BOARD line, and the board it saw
+fn logged_board(outputs: &AgentOutputs) -> Option<(usize, &str)> {
+    (0..2).find_map(|agent| {
+        let stderr = &outputs[agent].first()?.1;
+        stderr.lines().find_map(|line| line.strip_prefix(BOARD_PREFIX)).map(|line| (agent, line))
+    })
+}
+
+// A CodinGame game at its start, seen by player 0, and each agent's output lines
+fn codingame_game(frames: &[Json]) -> Result<(GameState, AgentOutputs), String> {
+    let outputs = codingame_outputs(frames);
+    let Some((agent, line)) = logged_board(&outputs) else {
+        return Err("the frames hold no input and neither agent wrote the BOARD line this bot writes on its first turn, the game cannot be played again".to_string());
+    };
+    let mut start = parse_board(line)?;
+    // The referee takes the board seen by player 0
+    if agent == 1 {
+        let seen = start;
+        start = GameState::new(&seen.config());
+        start.update(seen.enemy_matter, seen.my_matter, &tiles_seen_by(&seen, 1)).map_err(|err| format!("the BOARD line does not read: {:?}", err))?;
+    }
+    Ok((start, outputs))
+}
+
+// The turns `player` played in a CodinGame replay, the inputs rebuilt from the BOARD line
+fn codingame_turns(frames: &[Json], player: usize) -> Result<Vec<RecordedTurn>, String> {
+    let (start, outputs) = codingame_game(frames)?;
+    let mut referee = Referee::new(&start);
+    let mut turns = Vec::new();
+    for (k, (first, second)) in outputs[0].iter().zip(&outputs[1]).enumerate() {
+        if referee.is_over() {
+            break;
+        }
+        let stdout = if player == 0 { &first.0 } else { &second.0 };
+        turns.push(RecordedTurn { turn: k as i32 + 1, input: referee.input(player), stdout: stdout.clone() });
+        referee.step([&first.0, &second.0]);
+    }
+    Ok(turns)
+}
+
+impl Replay {
+    pub fn new(names: [&str; 2], seed: Option<u64>) -> Self {
+        Replay { seed, names: names.map(str::to_string), frames: Vec::new(), result: None }
+    }
+
+    // A turn about to be played on `game`, player 0's view of the board
+    pub fn record_turn(&mut self, game: &GameState, inputs: &[String; 2], outputs: &[String; 2], report: &TurnReport) {
+        let players = (0..2)
+            .map(|player| {
+                let rejected: Vec<&str> = report.rejected[player].iter().map(String::as_str).collect();
+                Json::object([("input", inputs[player].as_str().into()), ("stdout", outputs[player].as_str().into()), ("rejected", rejected.into())])
+            })
+            .collect();
+        let mut frame = board(game);
+        frame.push(("players", Json::Array(players)));
+        self.frames.push(Json::object(frame));
+    }
+
+    pub fn record_end(&mut self, game: &GameState, result: &GameResult) {
+        self.frames.push(Json::object(board(game)));
+        self.result = Some(result.clone());
+    }
+
+    // The seed of the recorded game, None when the file has none
+    pub fn recorded_seed(json: &Json) -> Option<u64> {
+        json.get("gameResult")?.get("seed")?.as_str()?.parse().ok()
+    }
+
+    // The seed this bot logged in a CodinGame replay, None when neither agent logged one
+    pub fn logged_seed(json: &Json) -> Option<u64> {
+        let frames = json.get("gameResult")?.get("frames")?.as_array()?;
+        codingame_outputs(frames).iter().flatten().find_map(|(_, stderr)| stderr.lines().find_map(|line| line.strip_prefix(SEED_PREFIX)?.trim().parse().ok()))
+    }
+
+    // A CodinGame replay written the way replays are written here, every turn played again with
+    // the local referee from the BOARD line. The agents keep their CodinGame names. A game cut
+    // short by a timeout or CodinGame's own end has no result.
+    pub fn from_codingame(json: &Json) -> Result<Self, String> {
+        let game_result = json.get("gameResult").ok_or("no gameResult")?;
+        let frames = game_result.get("frames").and_then(Json::as_array).ok_or("no gameResult.frames")?;
+        let (start, outputs) = codingame_game(frames)?;
+        let name = |agent: usize| {
+            let agents = game_result.get("agents").and_then(Json::as_array).unwrap_or_default();
+            let pseudo = agents.iter().find(|fields| fields.get("index").and_then(Json::as_f64) == Some(agent as f64)).and_then(|fields| fields.get("codingamer")?.get("pseudo")?.as_str());
+            pseudo.map_or_else(|| format!("agent {}", agent), str::to_string)
+        };
+        let names = [name(0), name(1)];
+        let mut replay = Replay::new([&names[0], &names[1]], Replay::logged_seed(json));
+        let mut referee = Referee::new(&start);
+        for (first, second) in outputs[0].iter().zip(&outputs[1]) {
+            if referee.is_over() {
+                break;
+            }
+            let inputs = [referee.input(0), referee.input(1)];
+            let before = referee.view(0).clone();
+            let report = referee.step([&first.0, &second.0]);
+            replay.record_turn(&before, &inputs, &[first.0.clone(), second.0.clone()], &report);
+        }
+        match referee.result() {
+            Some(result) => replay.record_end(referee.view(0), &result),
+            None => replay.frames.push(Json::object(board(referee.view(0)))),
+        }
+        Ok(replay)
+    }
+
+    // The agent that is this bot in a CodinGame replay, the one that wrote the BOARD line
+    pub fn logged_agent(json: &Json) -> Option<usize> {
+        let frames = json.get("gameResult")?.get("frames")?.as_array()?;
+        logged_board(&codingame_outputs(frames)).map(|(agent, _)| agent)
+    }
+
+    // The turns `player` played, in order, from a replay file's JSON, written here or downloaded
+    // from CodinGame
+    pub fn recorded_turns(json: &Json, player: usize) -> Result<Vec<RecordedTurn>, String> {
+        let frames = json.get("gameResult").and_then(|result| result.get("frames")).and_then(Json::as_array).ok_or("no gameResult.frames")?;
+        if !frames.iter().any(|frame| frame.get("players").is_some()) {
+            return codingame_turns(frames, player);
+        }
+        let mut turns = Vec::new();
+        // The final board has no players
+        for (k, frame) in frames.iter().enumerate() {
+            let Some(players) = frame.get("players").and_then(Json::as_array) else {
+                continue;
+            };
+            let turn = frame.get("turn").and_then(Json::as_f64).ok_or_else(|| format!("frame {} has no turn", k))? as i32;
+            let field = |name: &str| {
+                players.get(player).and_then(|fields| fields.get(name)).and_then(Json::as_str).map(str::to_string).ok_or_else(|| format!("frame {} has no {} for player {}", k, name, player))
+            };
+            turns.push(RecordedTurn { turn, input: field("input")?, stdout: field("stdout")? });
+        }
+        Ok(turns)
+    }
+
+    // Player 0's board as the given turn was about to be played, to resume the game from
+    pub fn recorded_board(json: &Json, turn: usize) -> Result<GameState, String> {
+        let frames = json.get("gameResult").and_then(|result| result.get("frames")).and_then(Json::as_array).ok_or("no gameResult.frames")?;
+        let frame = frames.iter().find(|frame| frame.get("turn").and_then(Json::as_f64) == Some(turn as f64)).ok_or_else(|| format!("no frame for turn {}", turn))?;
+        let number = |value: &Json| value.as_f64().map(|n| n as i32).ok_or("a number is missing");
+        let matter: Vec<i32> = frame.get("matter").and_then(Json::as_array).ok_or("the frame has no matter")?.iter().map(number).collect::<Result<_, _>>()?;
+        let rows = frame.get("grid").and_then(Json::as_array).ok_or("the frame has no grid")?;
+        let mut tiles = Vec::new();
+        for row in rows {
+            for tile in row.as_array().ok_or("a grid row is not an array")? {
+                let fields: Vec<i32> = tile.as_array().ok_or("a tile is not an array")?.iter().map(number).collect::<Result<_, _>>()?;
+                let &[scrap, owner, units, recycler] = fields.as_slice() else { return Err("a tile does not have 4 fields".to_string()) };
+                let owner = match owner {
+                    0 => Owner::Me,
+                    1 => Owner::Enemy,
+                    _ => Owner::Neutral,
+                };
+                tiles.push([scrap, owner.to_code(), units, recycler, 0, 0, 0]);
+            }
+        }
+        let (height, width) = (rows.len(), rows.first().and_then(Json::as_array).map_or(0, |row| row.len()));
+        let &[my_matter, enemy_matter] = matter.as_slice() else { return Err("the frame does not have 2 matter counts".to_string()) };
+        if width == 0 || tiles.len() != width * height {
+            return Err("the grid is not a rectangle".to_string());
+        }
+        derive_flags(&mut tiles, width);
+        let mut board = GameState::new(&GameConfig { width, height });
+        board.update(my_matter, enemy_matter, &tiles).map_err(|err| format!("the board of turn {} does not read: {:?}", turn, err))?;
+        Ok(board)
+    }
+
+    pub fn to_json(&self) -> Json {
+        let agents = (0..2).map(|k| Json::object([("index", k.into()), ("name", self.names[k].as_str().into())])).collect();
+        let mut game_result = Vec::new();
+        if let Some(seed) = self.seed {
+            // As text, JSON numbers lose precision above 2^53
+            game_result.push(("seed", seed.to_string().as_str().into()));
+        }
+        if let Some(result) = &self.result {
+            game_result.push(("end", format!("{:?}", result.end).as_str().into()));
+            game_result.push(("turns", result.turns.into()));
+        }
+        game_result.push(("agents", Json::Array(agents)));
+        game_result.push(("frames", Json::Array(self.frames.clone())));
+        if let Some(result) = &self.result {
+            let ranks = match result.winner() {
+                Some(winner) => vec![(winner != 0) as usize, (winner != 1) as usize],
+                None => vec![0, 0],
+            };
+            game_result.push(("scores", result.scores.to_vec().into()));
+            game_result.push(("ranks", ranks.into()));
+        }
+        Json::object([("gameResult", Json::object(game_result))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::ascii::format_board;
+    use crate::config_default::DEFAULT_CONFIG;
+    use crate::io::{self, InputStatus};
+    use crate::mapgen::MapGenerator;
+    use crate::referee::{Bot, StrategyBot};
+    use crate::strategy::Config;
+
+    const TURNS: usize = 12;
+
+    // A game played here, written the way CodinGame writes it: a frame per agent and turn, the
+    // given agent's first stderr with this bot's BOARD line. Each player's inputs come with it.
+    fn downloaded_game(logging_agent: usize) -> (Json, [Vec<RecordedTurn>; 2]) {
+        let mut referee = Referee::new(&MapGenerator::generate(3));
+        let mut bots = [0, 1].map(|player| StrategyBot::new(Config { seed: Some(player), ..DEFAULT_CONFIG }));
+        let mut frames = vec![Json::object([("keyframe", Json::Bool(true)), ("view", "".into())])];
+        let mut played: [Vec<RecordedTurn>; 2] = Default::default();
+        for turn in 1..=TURNS {
+            let inputs = [0, 1].map(|player| referee.input(player));
+            let outputs = [0, 1].map(|player| bots[player].play(&inputs[player]));
+            for (agent, (input, output)) in inputs.iter().zip(&outputs).enumerate() {
+                let mut stderr = String::new();
+                if turn == 1 && agent == logging_agent {
+                    let mut input = Cursor::new(input.as_str());
+                    let mut seen = GameState::new(&io::read_game(&mut input).unwrap());
+                    assert!(matches!(io::read_turn(&mut input, &mut seen), InputStatus::Ready));
+                    stderr = format!("{}{}\nSEED: {}\n", BOARD_PREFIX, format_board(&seen), agent);
+                }
+                let stdout = format!("{}\n", output);
+                frames.push(Json::object([("agentId", agent.into()), ("stdout", stdout.as_str().into()), ("stderr", stderr.as_str().into())]));
+                played[agent].push(RecordedTurn { turn: turn as i32, input: input.clone(), stdout });
+            }
+            referee.step([&outputs[0], &outputs[1]]);
+        }
+        (Json::object([("gameResult", Json::object([("frames", Json::Array(frames))]))]), played)
+    }
+
+    #[test]
+    fn codingame_inputs_are_rebuilt_from_the_logged_board() {
+        for logging_agent in 0..2 {
+            let (json, played) = downloaded_game(logging_agent);
+            for (player, turns) in played.iter().enumerate() {
+                assert_eq!(&Replay::recorded_turns(&json, player).unwrap(), turns);
+            }
+            assert_eq!(Replay::logged_seed(&json), Some(logging_agent as u64));
+        }
+    }
+
+    #[test]
+    fn codingame_replays_convert_to_the_same_turns() {
+        let (json, played) = downloaded_game(1);
+        let converted = Replay::from_codingame(&json).unwrap().to_json();
+        for (player, turns) in played.iter().enumerate() {
+            assert_eq!(&Replay::recorded_turns(&converted, player).unwrap(), turns);
+        }
+        assert_eq!(Replay::logged_agent(&json), Some(1));
+        assert_eq!(Replay::recorded_seed(&converted), Some(1));
+        let board = Replay::recorded_board(&converted, 1).unwrap();
+        assert_eq!(board.grid, MapGenerator::generate(3).grid);
+    }
+
+    // A short game on a small board with every line written out, so the sample file changes with
+    // the writer and the referee only. The enemy's builds are turned away.
+    const SAMPLE_BOARD: &str = "
+        2m1  2.  2.  2e1
+        2m   2.  2.  2e
+    ";
+    const SAMPLE_LINES: [[&str; 2]; 5] = [
+        ["MOVE 1 0 0 3 0", "WAIT"],
+        ["MOVE 1 1 0 3 0;SPAWN 1 0 1", "BUILD 3 0"],
+        ["MOVE 1 2 0 3 0;MOVE 1 0 1 3 1", "BUILD 0 0;MESSAGE hold"],
+        ["MOVE 1 1 1 3 1;SPAWN 1 2 0", "WAIT"],
+        ["MOVE 1 2 1 3 1;MOVE 1 2 0 3 0", "WAIT"],
+    ];
+    const SAMPLE_PATH: &str = "../../golden/replay-sample.json";
+
+    fn sample_replay() -> Json {
+        let mut referee = Referee::new(&GameState::from_ascii(SAMPLE_BOARD).unwrap());
+        let mut replay = Replay::new(["left", "right"], Some(0));
+        for lines in SAMPLE_LINES {
+            assert!(!referee.is_over(), "the sample game ended before its last line");
+            let inputs = [referee.input(0), referee.input(1)];
+            let before = referee.view(0).clone();
+            let report = referee.step(lines);
+            replay.record_turn(&before, &inputs, &lines.map(str::to_string), &report);
+        }
+        replay.record_end(referee.view(0), &referee.result().expect("the sample game is over"));
+        replay.to_json()
+    }
+
+    #[test]
+    fn the_sample_replay_is_what_the_writer_writes() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(SAMPLE_PATH);
+        let written = sample_replay().to_string();
+        if std::env::var_os("BLESS").is_some() {
+            std::fs::write(&path, format!("{}\n", written)).unwrap();
+        }
+        let sample = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(sample.trim_end(), written, "BLESS=1 writes the sample again");
+        let json: Json = sample.parse().unwrap();
+        let turns = Replay::recorded_turns(&json, 0).unwrap();
+        assert_eq!(turns.len(), Replay::recorded_turns(&json, 1).unwrap().len());
+        // Each frame's board is the one its input describes, the map size coming first
+        let mut seen: Option<GameState> = None;
+        for turn in &turns {
+            let mut input = Cursor::new(turn.input.as_str());
+            let seen = seen.get_or_insert_with(|| GameState::new(&io::read_game(&mut input).unwrap()));
+            assert!(matches!(io::read_turn(&mut input, seen), InputStatus::Ready));
+            assert_eq!(seen.grid, Replay::recorded_board(&json, turn.turn as usize).unwrap().grid, "turn {}", turn.turn);
+        }
+    }
+
+    #[test]
+    fn codingame_games_without_the_board_are_refused() {
+        let frames = Json::Array(vec![Json::object([("agentId", 0.into()), ("stdout", "WAIT\n".into()), ("stderr", "".into())])]);
+        let json = Json::object([("gameResult", Json::object([("frames", frames)]))]);
+        assert!(Replay::recorded_turns(&json, 0).unwrap_err().contains("BOARD"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// xorshift64*: small and fast, plenty for spawn placement, and nothing to bundle with the submission
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+// Spreads nearby seeds apart, xorshift streams from similar states start out similar
+pub(crate) fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The all-zero state never leaves zero
+        Rng { state: splitmix64(seed).max(1) }
+    }
+
+    // A different stream on every run
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Rng::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniform in the range, which must not be empty
+    pub fn gen_range(&mut self, range: Range<i32>) -> i32 {
+        assert!(range.start < range.end, "empty range {:?}", range);
+        let span = (range.end as i64 - range.start as i64) as u128;
+        // Multiply-shift rather than modulo, the bias is far too small to matter for spans this short
+        let offset = (self.next_u64() as u128 * span) >> 64;
+        (range.start as i64 + offset as i64) as i32
+    }
+
+    // True with probability p
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for k in (1..items.len()).rev() {
+            items.swap(k, self.gen_range(0..k as i32 + 1) as usize);
+        }
+    }
+
+    // An independent generator seeded from this one, so a seed still fixes everything derived from it
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
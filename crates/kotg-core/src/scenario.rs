@@ -0,0 +1,684 @@
+// Tactical test positions written as text, one directive per line, # starts a comment:
+//
+//   turn 12
+//   matter 40 30
+//   map
+//   5m2 5m  5.  5e1
+//   0   5M  5.  5e
+//   end
+//   expect spawn >= 3 at 1 0
+//   expect build == 0
+//   expect move >= 1 from 0 0 to 1 0
+//
+// The map rows are in the ascii module's format.
+// Several maps are consecutive turns the bot plays in order, for behaviours that build up over
+// turns, and the expectations check the last one. The turn counts on from one map to the next
+// unless given again, and matter carries over until given again. The bot's random choices are
+// seeded, so a scenario plays the same every run.
+// An expectation counts units for spawn and move, actions for build, optionally only those at
+// a tile, from a tile and/or to a tile, and compares the count with >=, <= or ==.
+//
+// A scenario can check the simulator instead of the bot: both players' actions are given, and
+// the board and matter the turn must end on, in any combination. Missing actions are none.
+//
+//   play BUILD 1 1;SPAWN 1 1 0
+//   enemy MOVE 2 3 0 0 0
+//   after
+//   1m  4m4 5e2 5e
+//   4m  4M  4.  5e
+//   end
+//   after matter 14 20
+//   infer
+//
+// With infer, the enemy's actions rebuilt from the board before and after the turn must be those
+// played, in any order. Moves need targets next to their tile to be rebuilt as written.
+//
+// Scenarios are also written back as text, after the minimizer cropped them or grassed tiles
+// over, and one can be made from a turn of a golden game, see bin/minimize.rs.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::action_set::ActionSet;
+use crate::actions::{format_actions, parse_actions, Action};
+use crate::ascii::{format_tile, parse_map, AsciiMap};
+use crate::game::{derive_flags, GameConfig, GameState, Location, Owner};
+use crate::io::parse_ints;
+use crate::inference::infer_enemy_actions;
+use crate::pos::Pos;
+use crate::sim::ActionSets;
+use crate::strategy::{Config, GreedyOutsideStrategy, Strategy};
+use crate::zobrist;
+
+const BOT_SEED: u64 = 0;
+
+#[derive(Debug)]
+pub struct ParseScenarioError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Spawn,
+    Build,
+    Move,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+    Exactly,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    pub text: String,
+    pub kind: ActionKind,
+    pub comparison: Comparison,
+    pub count: i32,
+    pub at: Option<Pos>,
+    pub from: Option<Pos>,
+    pub to: Option<Pos>,
+}
+
+impl Expectation {
+    // Units for spawns and moves, actions for builds, among those matching the tile filters
+    pub fn count(&self, actions: &[Action]) -> i32 {
+        let matches = |filter: Option<Pos>, pos: Pos| filter.is_none_or(|filter| filter == pos);
+        actions
+            .iter()
+            .map(|action| match *action {
+                Action::Spawn { amount, at } if self.kind == ActionKind::Spawn && matches(self.at, at) => amount.get(),
+                Action::Build { at } if self.kind == ActionKind::Build && matches(self.at, at) => 1,
+                Action::Move { amount, from, to } if self.kind == ActionKind::Move && matches(self.from, from) && matches(self.to, to) =>
+                    amount.get(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn holds(&self, actions: &[Action]) -> bool {
+        let count = self.count(actions);
+        match self.comparison {
+            Comparison::AtLeast => count >= self.count,
+            Comparison::AtMost => count <= self.count,
+            Comparison::Exactly => count == self.count,
+        }
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ActionKind::Spawn => "spawn",
+            ActionKind::Build => "build",
+            ActionKind::Move => "move",
+        };
+        let comparison = match self.comparison {
+            Comparison::AtLeast => ">=",
+            Comparison::AtMost => "<=",
+            Comparison::Exactly => "==",
+        };
+        write!(f, "{} {} {}", kind, comparison, self.count)?;
+        for (name, filter) in [("at", self.at), ("from", self.from), ("to", self.to)] {
+            if let Some(pos) = filter {
+                write!(f, " {} {} {}", name, pos.x(), pos.y())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Expectation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let [kind, comparison, count, filters @ ..] = words.as_slice() else {
+            return Err("expected `<spawn|build|move> <>=|<=|==> <count> [at|from|to x y]...`".to_string());
+        };
+        let kind = match *kind {
+            "spawn" => ActionKind::Spawn,
+            "build" => ActionKind::Build,
+            "move" => ActionKind::Move,
+            _ => return Err(format!("unknown action kind {:?}", kind)),
+        };
+        let comparison = match *comparison {
+            ">=" => Comparison::AtLeast,
+            "<=" => Comparison::AtMost,
+            "==" => Comparison::Exactly,
+            _ => return Err(format!("unknown comparison {:?}", comparison)),
+        };
+        let count = count.parse().map_err(|_| format!("bad count {:?}", count))?;
+        let mut expectation = Expectation { text: s.to_string(), kind, comparison, count, at: None, from: None, to: None };
+        for filter in filters.chunks(3) {
+            let &[name, x, y] = filter else {
+                return Err(format!("incomplete tile filter {:?}", filter.join(" ")));
+            };
+            let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+                return Err(format!("bad tile {} {}", x, y));
+            };
+            let slot = match (name, kind) {
+                ("at", ActionKind::Spawn | ActionKind::Build) => &mut expectation.at,
+                ("from", ActionKind::Move) => &mut expectation.from,
+                ("to", ActionKind::Move) => &mut expectation.to,
+                _ => return Err(format!("{:?} does not apply to {:?}", name, kind)),
+            };
+            *slot = Some(Pos::new(x, y));
+        }
+        Ok(expectation)
+    }
+}
+
+// A board the bot plays before the one checked
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub turn: usize,
+    pub my_matter: i32,
+    pub enemy_matter: i32,
+    pub tiles: Vec<[i32; 7]>,
+}
+
+#[derive(Debug)]
+pub struct Scenario {
+    pub width: usize,
+    pub height: usize,
+    pub turn: usize,
+    pub my_matter: i32,
+    pub enemy_matter: i32,
+    // Referee protocol fields per tile, row-major
+    pub tiles: Vec<[i32; 7]>,
+    pub earlier: Vec<Frame>,
+    pub expectations: Vec<Expectation>,
+    // The played turn, only for scenarios checking the simulator
+    pub played: ActionSets,
+    pub after: Option<AsciiMap>,
+    pub after_matter: Option<(i32, i32)>,
+    pub check_inference: bool,
+}
+
+// Lines of a map block with their line numbers
+type Rows<'a> = Vec<(usize, &'a str)>;
+
+enum Block {
+    Map,
+    After,
+}
+
+impl FromStr for Scenario {
+    type Err = ParseScenarioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut turn = 1;
+        let mut matter = (0, 0);
+        // Each map with the turn and matter it was given
+        let mut maps: Vec<(usize, (i32, i32), Rows)> = Vec::new();
+        let mut after_rows: Rows = Vec::new();
+        let mut after_seen = false;
+        let mut expectations = Vec::new();
+        let mut played = ActionSets::default();
+        let mut after_matter = None;
+        let mut check_inference = false;
+        let mut block = None;
+        for (k, line) in s.lines().enumerate() {
+            let err = |reason: String| ParseScenarioError { line: k + 1, reason };
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(inside) = &block {
+                match (line, inside) {
+                    ("end", _) => block = None,
+                    (_, Block::Map) => {
+                        if let Some((_, _, rows)) = maps.last_mut() {
+                            rows.push((k + 1, line));
+                        }
+                    }
+                    (_, Block::After) => after_rows.push((k + 1, line)),
+                }
+                continue;
+            }
+            let (directive, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match directive {
+                "turn" => turn = rest.trim().parse().map_err(|_| err(format!("bad turn {:?}", rest)))?,
+                "matter" => match parse_ints(rest).as_deref() {
+                    Some(&[mine, theirs]) => matter = (mine, theirs),
+                    _ => return Err(err(format!("bad matter {:?}", rest))),
+                },
+                "map" if rest.is_empty() => {
+                    if turn == 0 {
+                        return Err(err("turns start at 1".to_string()));
+                    }
+                    maps.push((turn, matter, Vec::new()));
+                    turn += 1;
+                    block = Some(Block::Map);
+                }
+                "expect" => expectations.push(rest.parse().map_err(err)?),
+                "play" => played.mine = parse_actions(rest).map_err(|action_err| err(action_err.to_string()))?,
+                "enemy" => played.enemy = parse_actions(rest).map_err(|action_err| err(action_err.to_string()))?,
+                "after" if rest.is_empty() && !after_seen => {
+                    after_seen = true;
+                    block = Some(Block::After);
+                }
+                "after" => match rest.strip_prefix("matter").and_then(parse_ints).as_deref() {
+                    Some(&[mine, theirs]) => after_matter = Some((mine, theirs)),
+                    _ => return Err(err(format!("bad after {:?}", rest))),
+                },
+                "infer" if rest.is_empty() => check_inference = true,
+                _ => return Err(err(format!("unknown directive {:?}", directive))),
+            }
+        }
+        let err = |reason: &str| ParseScenarioError { line: s.lines().count(), reason: reason.to_string() };
+        if block.is_some() {
+            return Err(err("map or after without end"));
+        }
+        let simulated = after_seen || after_matter.is_some();
+        let played_any = !played.mine.is_empty() || !played.enemy.is_empty();
+        if (played_any || check_inference) && !simulated {
+            return Err(err("play, enemy and infer need an after map or after matter"));
+        }
+        if simulated && !expectations.is_empty() {
+            return Err(err("expect checks the bot, not a played turn"));
+        }
+        if simulated && maps.len() > 1 {
+            return Err(err("a played turn starts from a single map"));
+        }
+        let Some((turn, (my_matter, enemy_matter), map_rows)) = maps.pop() else {
+            return Err(err("no map"));
+        };
+        let parse_rows = |rows: &[(usize, &str)]| {
+            parse_map(rows.iter().map(|&(_, row)| row))
+                .map_err(|map_err| ParseScenarioError { line: rows.get(map_err.row).map_or(0, |row| row.0), reason: map_err.reason })
+        };
+        let AsciiMap { width, height, tiles } = parse_rows(&map_rows)?;
+        let mut earlier = Vec::new();
+        for (turn, (my_matter, enemy_matter), rows) in maps {
+            let map = parse_rows(&rows)?;
+            if (map.width, map.height) != (width, height) {
+                return Err(ParseScenarioError { line: rows[0].0, reason: format!("map is {}x{}, the last one {}x{}", map.width, map.height, width, height) });
+            }
+            earlier.push(Frame { turn, my_matter, enemy_matter, tiles: map.tiles });
+        }
+        let after = if after_seen { Some(parse_rows(&after_rows)?) } else { None };
+        Ok(Scenario {
+            width,
+            height,
+            turn,
+            my_matter,
+            enemy_matter,
+            tiles,
+            earlier,
+            expectations,
+            played,
+            after,
+            after_matter,
+            check_inference,
+        })
+    }
+}
+
+const GRASS: [i32; 7] = [0, -1, 0, 0, 0, 0, 0];
+
+// The input of each turn of a golden game, the first one with the map size line in front
+pub fn split_turns(input: &str) -> Result<Vec<String>, String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let Some(&[width, height]) = lines.first().and_then(|line| parse_ints(line)).as_deref().filter(|size| size.iter().all(|&side| side > 0)) else {
+        return Err("the first line is not a map size".to_string());
+    };
+    let turn_lines = 1 + (width * height) as usize;
+    if !(lines.len() - 1).is_multiple_of(turn_lines) {
+        return Err(format!("{} lines after the map size, not a whole number of {}-line turns", lines.len() - 1, turn_lines));
+    }
+    let mut turns: Vec<String> = lines[1..].chunks(turn_lines).map(|chunk| chunk.iter().map(|line| format!("{}\n", line)).collect()).collect();
+    if let Some(first) = turns.first_mut() {
+        first.insert_str(0, &format!("{}\n", lines[0]));
+    }
+    Ok(turns)
+}
+
+fn write_rows(f: &mut fmt::Formatter, width: usize, tiles: &[[i32; 7]]) -> fmt::Result {
+    for row in tiles.chunks(width) {
+        let tokens: Vec<String> = row
+            .iter()
+            .map(|tile| {
+                let owner = match tile[1] {
+                    1 => Owner::Me,
+                    0 => Owner::Enemy,
+                    _ => Owner::Neutral,
+                };
+                format_tile(&Location { scrap_amount: tile[0], owner, units: tile[2], recycler: tile[3] == 1, ..Location::default() })
+            })
+            .collect();
+        writeln!(f, "{}", tokens.join(" "))?;
+    }
+    writeln!(f, "end")
+}
+
+// The rectangle's tiles, the flags derived again
+fn crop(tiles: &[[i32; 7]], width: usize, (left, top, new_width, new_height): (usize, usize, usize, usize)) -> Vec<[i32; 7]> {
+    let mut cropped: Vec<[i32; 7]> = (top..top + new_height).flat_map(|y| tiles[y * width + left..y * width + left + new_width].iter().copied()).collect();
+    derive_flags(&mut cropped, new_width);
+    cropped
+}
+
+// The action with its tiles moved by `shift`, None when one of them has no place
+fn shifted(action: &Action, shift: &impl Fn(Pos) -> Option<Pos>) -> Option<Action> {
+    Some(match *action {
+        Action::Move { amount, from, to } => Action::Move { amount, from: shift(from)?, to: shift(to)? },
+        Action::Build { at } => Action::Build { at: shift(at)? },
+        Action::Spawn { amount, at } => Action::Spawn { amount, at: shift(at)? },
+        Action::Wait | Action::Message { .. } => action.clone(),
+    })
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let last = Frame { turn: self.turn, my_matter: self.my_matter, enemy_matter: self.enemy_matter, tiles: self.tiles.clone() };
+        for frame in self.earlier.iter().chain([&last]) {
+            writeln!(f, "turn {}\nmatter {} {}\nmap", frame.turn, frame.my_matter, frame.enemy_matter)?;
+            write_rows(f, self.width, &frame.tiles)?;
+        }
+        for expectation in &self.expectations {
+            writeln!(f, "expect {}", expectation)?;
+        }
+        if !self.played.mine.is_empty() {
+            writeln!(f, "play {}", format_actions(&self.played.mine))?;
+        }
+        if !self.played.enemy.is_empty() {
+            writeln!(f, "enemy {}", format_actions(&self.played.enemy))?;
+        }
+        if let Some(after) = &self.after {
+            writeln!(f, "after")?;
+            write_rows(f, after.width, &after.tiles)?;
+        }
+        if let Some((mine, theirs)) = self.after_matter {
+            writeln!(f, "after matter {} {}", mine, theirs)?;
+        }
+        if self.check_inference {
+            writeln!(f, "infer")?;
+        }
+        Ok(())
+    }
+}
+
+impl Scenario {
+    pub fn game(&self) -> GameState {
+        let mut game = GameState::new(&GameConfig { width: self.width, height: self.height });
+        game.turn = self.turn - 1;
+        let result = game.update(self.my_matter, self.enemy_matter, &self.tiles);
+        debug_invariant!(result.is_ok(), "scenario tiles rejected: {:?}", result);
+        game
+    }
+
+    // The actions a fresh bot plays on the position, after the earlier maps, and the
+    // expectations they break
+    pub fn run(&self) -> (Vec<Action>, Vec<&Expectation>) {
+        let mut game = GameState::new(&GameConfig { width: self.width, height: self.height });
+        let mut strategy = GreedyOutsideStrategy::new(&game, Config { seed: Some(BOT_SEED), ..Config::default() });
+        let last = Frame { turn: self.turn, my_matter: self.my_matter, enemy_matter: self.enemy_matter, tiles: self.tiles.clone() };
+        let mut planned = Vec::new();
+        for frame in self.earlier.iter().chain([&last]) {
+            // Read like io::read_turn does
+            let previous = game.clone();
+            game.turn = frame.turn - 1;
+            let result = game.update(frame.my_matter, frame.enemy_matter, &frame.tiles);
+            debug_invariant!(result.is_ok(), "scenario tiles rejected: {:?}", result);
+            game.enemy_actions = if previous.turn > 0 { infer_enemy_actions(&previous, &game) } else { Vec::new() };
+            planned = strategy.plan(&game);
+        }
+        // Resolved like the bot does before printing
+        let mut action_set = ActionSet::new(planned);
+        action_set.resolve(&game);
+        let actions = action_set.into_actions();
+        let failed = self.expectations.iter().filter(|expectation| !expectation.holds(&actions)).collect();
+        (actions, failed)
+    }
+
+    // How the board the simulator plays the turn to differs from the after map and matter,
+    // whether undoing the turn played in place restores the position, whether the hash kept up
+    // to date along the way is still right, and with infer whether the enemy's actions can be
+    // rebuilt from the two boards. None for scenarios checking the bot.
+    pub fn check_simulation(&self) -> Option<Vec<String>> {
+        if self.after.is_none() && self.after_matter.is_none() {
+            return None;
+        }
+        let next = self.game().apply(&self.played);
+        let mut mismatches = Vec::new();
+        // Played in place, the turn must reach the same state and undo must restore it exactly
+        let mut in_place = self.game();
+        let before = in_place.clone();
+        let delta = in_place.apply_delta(&self.played);
+        if in_place != next {
+            mismatches.push("apply_delta ends on another state than simulate".to_string());
+        }
+        in_place.undo(delta);
+        if in_place != before {
+            mismatches.push("undo does not restore the position".to_string());
+        }
+        if next.hash() != zobrist::full_hash(&next) {
+            mismatches.push("incremental hash differs from the hash computed from scratch".to_string());
+        }
+        if self.check_inference {
+            let sorted = |actions: &[Action]| {
+                let mut texts: Vec<String> = actions.iter().map(Action::to_string).collect();
+                texts.sort();
+                texts
+            };
+            let inferred = infer_enemy_actions(&self.game(), &next);
+            if sorted(&inferred) != sorted(&self.played.enemy) {
+                mismatches.push(format!("inferred enemy actions {}", format_actions(&inferred)));
+            }
+        }
+        if let Some(after) = &self.after {
+            let mut expected = GameState::new(&GameConfig { width: after.width, height: after.height });
+            if (after.width, after.height) != (next.width(), next.height()) {
+                mismatches.push(format!("after map is {}x{}, the board {}x{}", after.width, after.height, next.width(), next.height()));
+            }
+            else if let Err(owner_err) = expected.update(0, 0, &after.tiles) {
+                mismatches.push(format!("after map: {}", owner_err));
+            }
+            else {
+                for pos in next.positions() {
+                    let (wanted, got) = (format_tile(expected.loc(pos)), format_tile(next.loc(pos)));
+                    if wanted != got {
+                        mismatches.push(format!("tile {:?}: expected {}, got {}", pos, wanted, got));
+                    }
+                }
+            }
+        }
+        if let Some((mine, theirs)) = self.after_matter.filter(|&matter| matter != (next.my_matter, next.enemy_matter)) {
+            mismatches.push(format!("matter: expected {} {}, got {} {}", mine, theirs, next.my_matter, next.enemy_matter));
+        }
+        Some(mismatches)
+    }
+
+    // The position of a golden game's turn, counted from 1, the turns before it as earlier maps,
+    // expecting the line the game's .out holds for it action for action
+    pub fn from_golden(turns: &[String], turn: usize, expected: &str) -> Result<Scenario, String> {
+        let frames: Vec<Vec<Vec<i32>>> = turns.iter().map(|input| input.lines().map(|line| parse_ints(line).unwrap_or_default()).collect()).collect();
+        let (Some(&[width, height]), true) = (frames.first().and_then(|lines| lines.first()).map(Vec::as_slice), (1..=turns.len()).contains(&turn)) else {
+            return Err(format!("no turn {} in a game of {}", turn, turns.len()));
+        };
+        let (width, height) = (width as usize, height as usize);
+        let mut played = Vec::new();
+        for (k, lines) in frames[..turn].iter().enumerate() {
+            let lines = if k == 0 { &lines[1..] } else { &lines[..] };
+            let tiles: Option<Vec<[i32; 7]>> = lines[1..].iter().map(|fields| <[i32; 7]>::try_from(fields.as_slice()).ok()).collect();
+            let (Some(&[my_matter, enemy_matter]), Some(tiles)) = (lines.first().map(Vec::as_slice), tiles) else {
+                return Err(format!("turn {} does not read", k + 1));
+            };
+            played.push(Frame { turn: k + 1, my_matter, enemy_matter, tiles });
+        }
+        let Some(last) = played.pop() else { unreachable!() };
+        let actions = parse_actions(expected).map_err(|err| format!("the expected line does not read: {}", err))?;
+        // The count each filter gets in the expected line, exactly
+        let exactly = |kind, at, from, to| {
+            let mut expectation = Expectation { text: String::new(), kind, comparison: Comparison::Exactly, count: 0, at, from, to };
+            expectation.count = expectation.count(&actions);
+            expectation.text = expectation.to_string();
+            expectation
+        };
+        let mut expectations: Vec<Expectation> = [ActionKind::Spawn, ActionKind::Build, ActionKind::Move].into_iter().map(|kind| exactly(kind, None, None, None)).collect();
+        for action in &actions {
+            let expectation = match *action {
+                Action::Spawn { at, .. } => exactly(ActionKind::Spawn, Some(at), None, None),
+                Action::Build { at } => exactly(ActionKind::Build, Some(at), None, None),
+                Action::Move { from, to, .. } => exactly(ActionKind::Move, None, Some(from), Some(to)),
+                Action::Wait | Action::Message { .. } => continue,
+            };
+            if !expectations.iter().any(|known| known.text == expectation.text) {
+                expectations.push(expectation);
+            }
+        }
+        Ok(Scenario {
+            width,
+            height,
+            turn: last.turn,
+            my_matter: last.my_matter,
+            enemy_matter: last.enemy_matter,
+            tiles: last.tiles,
+            earlier: played,
+            expectations,
+            played: ActionSets::default(),
+            after: None,
+            after_matter: None,
+            check_inference: false,
+        })
+    }
+
+    // How the scenario fails, in terms its cropped copies can be compared on: the expectations
+    // broken by their place in the file, or the kinds of mismatch the simulator shows. Empty when
+    // it passes.
+    pub fn failures(&self) -> Vec<String> {
+        if let Some(mismatches) = self.check_simulation() {
+            let mut kinds: Vec<String> = mismatches.iter().filter_map(|mismatch| mismatch.split(|c: char| c.is_whitespace() || c == ':').next()).map(str::to_string).collect();
+            kinds.sort();
+            kinds.dedup();
+            return kinds;
+        }
+        let (actions, _) = self.run();
+        self.expectations.iter().enumerate().filter(|(_, expectation)| !expectation.holds(&actions)).map(|(k, _)| format!("expectation {}", k + 1)).collect()
+    }
+
+    // Every map cropped to the rectangle, the tiles the expectations and played actions name
+    // moved with it. None when the rectangle is empty or leaves one of those tiles out.
+    pub fn cropped(&self, left: usize, top: usize, width: usize, height: usize) -> Option<Scenario> {
+        if width == 0 || height == 0 || left + width > self.width || top + height > self.height {
+            return None;
+        }
+        let rect = (left, top, width, height);
+        let shift = |pos: Pos| (pos.x() >= left && pos.x() < left + width && pos.y() >= top && pos.y() < top + height).then(|| Pos::new(pos.x() - left, pos.y() - top));
+        let mut expectations = Vec::new();
+        for expectation in &self.expectations {
+            let mut moved = expectation.clone();
+            for filter in [&mut moved.at, &mut moved.from, &mut moved.to] {
+                if let Some(pos) = *filter {
+                    *filter = Some(shift(pos)?);
+                }
+            }
+            moved.text = moved.to_string();
+            expectations.push(moved);
+        }
+        let shift_all = |actions: &[Action]| actions.iter().map(|action| shifted(action, &shift)).collect::<Option<Vec<Action>>>();
+        Some(Scenario {
+            width,
+            height,
+            turn: self.turn,
+            my_matter: self.my_matter,
+            enemy_matter: self.enemy_matter,
+            tiles: crop(&self.tiles, self.width, rect),
+            earlier: self.earlier.iter().map(|frame| Frame { tiles: crop(&frame.tiles, self.width, rect), ..*frame }).collect(),
+            expectations,
+            played: ActionSets { mine: shift_all(&self.played.mine)?, enemy: shift_all(&self.played.enemy)? },
+            after: self.after.as_ref().map(|after| AsciiMap { width, height, tiles: crop(&after.tiles, after.width, rect) }),
+            after_matter: self.after_matter,
+            check_inference: self.check_inference,
+        })
+    }
+
+    // The tile turned to grass on every map. None when it already is on the checked one, or when
+    // an expectation or a played action names it.
+    pub fn grassed(&self, pos: Pos) -> Option<Scenario> {
+        let named = self.expectations.iter().any(|expectation| [expectation.at, expectation.from, expectation.to].contains(&Some(pos)))
+            || self.played.mine.iter().chain(&self.played.enemy).any(|action| shifted(action, &|at| (at != pos).then_some(at)).is_none());
+        let k = pos.index(self.width);
+        if named || self.tiles[k][0] == 0 {
+            return None;
+        }
+        let grass = |tiles: &[[i32; 7]], width: usize| {
+            let mut tiles = tiles.to_vec();
+            tiles[k] = GRASS;
+            derive_flags(&mut tiles, width);
+            tiles
+        };
+        Some(Scenario {
+            tiles: grass(&self.tiles, self.width),
+            earlier: self.earlier.iter().map(|frame| Frame { tiles: grass(&frame.tiles, self.width), ..*frame }).collect(),
+            expectations: self.expectations.clone(),
+            played: ActionSets { mine: self.played.mine.clone(), enemy: self.played.enemy.clone() },
+            after: self.after.as_ref().map(|after| AsciiMap { width: after.width, height: after.height, tiles: grass(&after.tiles, after.width) }),
+            ..*self
+        })
+    }
+
+    // Without the first of the earlier maps, None when there are none
+    pub fn without_earliest(&self) -> Option<Scenario> {
+        let (_, earlier) = self.earlier.split_first()?;
+        let cropped = self.cropped(0, 0, self.width, self.height)?;
+        Some(Scenario { earlier: earlier.to_vec(), ..cropped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    // The bot spawns on the free tile next to the front, not at 0 0
+    const FAILING: &str = "
+        matter 100 0
+        map
+        5m  5m  5.  5.  5.  5e
+        5m  5m  0   5.  5.  5e
+        end
+        expect spawn == 1
+        expect spawn == 1 at 0 0
+    ";
+
+    #[test]
+    fn scenarios_written_back_read_the_same() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../scenarios");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let scenario: Scenario = fs::read_to_string(&path).unwrap().parse().unwrap();
+            let written = scenario.to_string();
+            let again: Scenario = written.parse().unwrap_or_else(|err| panic!("{}: {}\n{}", path.display(), err, written));
+            assert_eq!(again.to_string(), written, "{}", path.display());
+            assert_eq!(again.failures(), scenario.failures(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn cropping_moves_the_named_tiles_and_keeps_the_failure() {
+        let scenario: Scenario = FAILING.parse().unwrap();
+        assert_eq!(scenario.failures(), vec!["expectation 2"]);
+        assert!(scenario.cropped(1, 0, 5, 2).is_none());
+        let cropped = scenario.cropped(0, 0, 3, 1).unwrap();
+        assert_eq!((cropped.width, cropped.height), (3, 1));
+        assert_eq!(cropped.expectations[1].at, Some(Pos::new(0, 0)));
+        assert_eq!(cropped.failures(), scenario.failures());
+        assert!(scenario.grassed(Pos::new(0, 0)).is_none());
+        assert!(scenario.grassed(Pos::new(2, 1)).is_none());
+        let grassed = scenario.grassed(Pos::new(5, 1)).unwrap();
+        assert_eq!(grassed.tiles[11], GRASS);
+    }
+}
@@ -0,0 +1,116 @@
+// One turn looked ahead with the simulator. A line of mine is played in place against a few
+// replies the enemy could make, each undone after, and is worth the evaluation of the worst
+// board they leave: turns are simultaneous, so the reply is not known when the line is chosen
+// and the safe line is the one whose worst case is best. The boards reached are evaluated once,
+// cached by their Zobrist hash: different lines often meet on the same board, a diverted unit
+// dying on the way as it would have where it was sent first, or a reply the line makes no
+// difference to.
+use crate::actions::{Action, Amount};
+use crate::analysis::Analysis;
+use crate::game::{GameState, Owner};
+use crate::pos::Pos;
+use crate::sim::ActionSets;
+use crate::transposition::TranspositionTable;
+
+// What the enemy could answer: nothing, every stack onto my weakest tile next to it, every stack
+// onto a neutral tile next to it, and for each of the given tiles of theirs, the stacks next to
+// it piling in to defend it
+pub fn enemy_replies(game: &GameState, defended: &[Pos]) -> Vec<Vec<Action>> {
+    let stacks: Vec<(Pos, Amount)> = game.iter_owned(Owner::Enemy)
+        .filter_map(|(pos, loc)| Amount::new(loc.units).map(|amount| (pos, amount)))
+        .collect();
+    let each_stack = |target: &dyn Fn(Pos) -> Option<Pos>| -> Vec<Action> {
+        stacks.iter().filter_map(|&(from, amount)| target(from).map(|to| Action::Move { amount, from, to })).collect()
+    };
+    let open = |to: Pos, owner: Owner| game.passable(to) && game.loc(to).owner == owner;
+    let mut replies = vec![
+        Vec::new(),
+        each_stack(&|from| game.neighbors(from).filter(|&to| open(to, Owner::Me)).min_by_key(|&to| game.loc(to).units)),
+        each_stack(&|from| game.neighbors(from).find(|&to| open(to, Owner::Neutral))),
+    ];
+    for &at in defended {
+        replies.push(each_stack(&|from| (from.manhattan(at) == 1).then_some(at)));
+    }
+    let mut distinct: Vec<Vec<Action>> = Vec::new();
+    for reply in replies {
+        if !distinct.contains(&reply) {
+            distinct.push(reply);
+        }
+    }
+    distinct
+}
+
+// The line's evaluation against its worst reply, each played on `game` and undone
+pub fn worst_case(game: &mut GameState, line: &[Action], replies: &[Vec<Action>], table: &mut TranspositionTable) -> i32 {
+    replies
+        .iter()
+        .map(|reply| {
+            let delta = game.apply_delta(&ActionSets { mine: line.to_vec(), enemy: reply.clone() });
+            let evaluation = match table.probe(game.hash()) {
+                Some(entry) => entry.eval,
+                None => {
+                    let evaluation = Analysis::new(game).evaluation();
+                    table.store(game.hash(), 0, evaluation, Vec::new());
+                    evaluation
+                }
+            };
+            game.undo(delta);
+            evaluation
+        })
+        .min()
+        .unwrap_or_else(|| Analysis::new(game).evaluation())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::parse_actions;
+
+    // Two stacks face each other across the middle, the enemy's with a neutral tile behind it
+    const BOARD: &str = "
+        5m   5m2  5e1  5.
+        5M   5m   5e   5E
+    ";
+
+    #[test]
+    fn replies_start_with_waiting_and_are_distinct() {
+        let game = GameState::from_ascii(BOARD).unwrap();
+        let replies = enemy_replies(&game, &[Pos::new(2, 0)]);
+        assert_eq!(replies[0], Vec::new());
+        assert!(replies.contains(&parse_actions("MOVE 1 2 0 1 0").unwrap()));
+        assert!(replies.contains(&parse_actions("MOVE 1 2 0 3 0").unwrap()));
+        for (k, reply) in replies.iter().enumerate() {
+            assert!(!replies[..k].contains(reply));
+        }
+    }
+
+    #[test]
+    fn the_worst_reply_counts_and_the_board_is_left_alone() {
+        let before = GameState::from_ascii(BOARD).unwrap();
+        let mut game = before.clone();
+        let line = parse_actions("MOVE 2 1 0 2 0").unwrap();
+        let replies = enemy_replies(&game, &[Pos::new(2, 0)]);
+        let mut table = TranspositionTable::new(64);
+        let worst = worst_case(&mut game, &line, &replies, &mut table);
+        assert_eq!(game, before);
+        let each: Vec<i32> = replies.iter().map(|reply| worst_case(&mut game, &line, std::slice::from_ref(reply), &mut TranspositionTable::new(64))).collect();
+        assert_eq!(Some(worst), each.iter().copied().min());
+    }
+
+    #[test]
+    fn boards_reached_are_cached_by_their_hash() {
+        let mut game = GameState::from_ascii(BOARD).unwrap();
+        let line = parse_actions("MOVE 2 1 0 2 0").unwrap();
+        let replies = enemy_replies(&game, &[Pos::new(2, 0)]);
+        let mut table = TranspositionTable::new(64);
+        table.new_search();
+        let worst = worst_case(&mut game, &line, &replies, &mut table);
+        for reply in &replies {
+            let reached = game.simulate(&line, reply);
+            let entry = table.probe(reached.hash()).unwrap();
+            assert_eq!(entry.eval, Analysis::new(&reached).evaluation());
+            assert!(entry.eval >= worst);
+        }
+        assert_eq!(worst_case(&mut game, &line, &replies, &mut table), worst);
+    }
+}
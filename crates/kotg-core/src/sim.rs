@@ -0,0 +1,539 @@
+// The referee's rules for one turn: both players' actions applied to a board give the board the
+// next turn starts on. Each phase is a function, run by simulate in the engine's order: builds,
+// then moves and spawns together, then units of both players on a tile destroy each other one
+// for one and the survivors take the tile, then recyclers harvest, tiles out of scrap turn to
+// grass with whatever stood on them, and both players get their income. Actions the referee
+// would reject are skipped. Units move one step toward their MOVE target, on the path the
+// engine would pick.
+use std::collections::VecDeque;
+
+use crate::actions::Action;
+use crate::budget::{RECYCLER_COST, UNIT_COST};
+use crate::game::{derive_flags, GameState, Location, Owner, Side, UNREACHABLE};
+use crate::grid::Grid;
+use crate::pos::{Direction, Pos};
+use crate::zobrist;
+
+// Matter every player gets each turn on top of the harvest
+pub const BASE_INCOME: i32 = 10;
+
+const PLAYERS: [Owner; 2] = [Owner::Me, Owner::Enemy];
+
+// Both players' actions for one turn
+#[derive(Debug, Default)]
+pub struct ActionSets {
+    pub mine: Vec<Action>,
+    pub enemy: Vec<Action>,
+}
+
+// What one apply_delta changed: the tiles it touched and the state fields as they were
+#[derive(Debug)]
+pub struct Delta {
+    tiles: Vec<(Pos, Location)>,
+    turn: usize,
+    my_side: Option<Side>,
+    my_matter: i32,
+    enemy_matter: i32,
+    my_robots: Vec<Pos>,
+    changed: Vec<Pos>,
+    hash: u64,
+}
+
+impl Delta {
+    // Keeps the tile as it was before the turn, the first time the turn is about to change it
+    fn save(&mut self, grid: &Grid<Location>, pos: Pos) {
+        if !self.tiles.iter().any(|&(at, _)| at == pos) {
+            self.tiles.push((pos, grid[pos].clone()));
+        }
+    }
+
+    // The tiles the turn touched, as they were before it
+    pub fn tiles(&self) -> impl Iterator<Item = (Pos, &Location)> {
+        self.tiles.iter().map(|(pos, loc)| (*pos, loc))
+    }
+}
+
+pub(crate) fn protocol_fields(loc: &Location) -> [i32; 7] {
+    [
+        loc.scrap_amount,
+        loc.owner.to_code(),
+        loc.units,
+        loc.recycler as i32,
+        loc.can_build as i32,
+        loc.can_spawn as i32,
+        loc.in_range_of_recycler as i32,
+    ]
+}
+
+// A tile while the turn resolves, the players' units kept apart until they fight
+#[derive(Debug, Clone)]
+struct SimTile {
+    scrap: i32,
+    owner: Owner,
+    recycler: bool,
+    // Indexed like PLAYERS
+    units: [i32; 2],
+}
+
+impl SimTile {
+    fn passable(&self) -> bool {
+        self.scrap > 0 && !self.recycler
+    }
+}
+
+// The order the engine lists a tile's neighbors in, which breaks ties between equally short paths
+const ENGINE_DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Up, Direction::Right, Direction::Down];
+
+fn bfs_from(width: usize, height: usize, passable: &impl Fn(Pos) -> bool, start: Pos) -> Grid<u16> {
+    let mut dist = Grid::new(width, height, UNREACHABLE);
+    let mut queue = VecDeque::from([start]);
+    dist[start] = 0;
+    while let Some(pos) = queue.pop_front() {
+        for direction in ENGINE_DIRECTIONS {
+            let Some(next) = direction.step(pos, width, height) else { continue };
+            if dist[next] == UNREACHABLE && passable(next) {
+                dist[next] = dist[pos] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    dist
+}
+
+// The tile the referee moves units to when ordered from `from` toward `to`: one step along a
+// shortest path over passable tiles, the first such neighbor in the engine's order. A target
+// out of reach, grass and recyclers included, is replaced by the reachable tile closest to it,
+// the closest to `from` among those, then the first in row-major order.
+// None when the units stay.
+pub(crate) fn engine_step(width: usize, height: usize, passable: impl Fn(Pos) -> bool, from: Pos, to: Pos) -> Option<Pos> {
+    let contains = |pos: Pos| pos.x() < width && pos.y() < height;
+    if from == to || !contains(from) || !contains(to) {
+        return None;
+    }
+    let from_dist = bfs_from(width, height, &passable, from);
+    let goal = if from_dist[to] != UNREACHABLE {
+        to
+    }
+    else {
+        from_dist
+            .iter_with_pos()
+            .filter(|&(_, &dist)| dist != UNREACHABLE)
+            .min_by_key(|&(pos, &dist)| (pos.manhattan(to), dist))
+            .map(|(pos, _)| pos)?
+    };
+    if goal == from {
+        return None;
+    }
+    let goal_dist = bfs_from(width, height, &passable, goal);
+    ENGINE_DIRECTIONS
+        .iter()
+        .filter_map(|direction| direction.step(from, width, height))
+        .find(|&next| goal_dist[next] != UNREACHABLE && goal_dist[next] + 1 == goal_dist[from])
+}
+
+// Recyclers go up on the builder's tiles free of units, before anything moves
+fn build(tiles: &mut Grid<SimTile>, actions: [&[Action]; 2], matter: &mut [i32; 2]) {
+    for p in 0..2 {
+        for action in actions[p] {
+            let Action::Build { at } = *action else { continue };
+            let Some(tile) = tiles.get_mut(at) else { continue };
+            if tile.owner == PLAYERS[p] && tile.passable() && tile.units == [0, 0] && matter[p] >= RECYCLER_COST {
+                tile.recycler = true;
+                matter[p] -= RECYCLER_COST;
+            }
+        }
+    }
+}
+
+// Units step toward their targets around the new recyclers, and spawns appear on the spawner's
+// tiles. Everything lands once every order has been read, so no unit moves twice and spawned
+// units stay put.
+fn move_and_spawn(tiles: &mut Grid<SimTile>, actions: [&[Action]; 2], matter: &mut [i32; 2]) {
+    let (width, height) = (tiles.width(), tiles.height());
+    let mut arrivals = Grid::new(width, height, [0; 2]);
+    for p in 0..2 {
+        for action in actions[p] {
+            match *action {
+                Action::Move { amount, from, to } => {
+                    if !tiles.contains(from) || tiles[from].owner != PLAYERS[p] {
+                        continue;
+                    }
+                    let n = amount.get().min(tiles[from].units[p]);
+                    let Some(next) = engine_step(width, height, |pos| tiles[pos].passable(), from, to).filter(|_| n > 0) else { continue };
+                    tiles[from].units[p] -= n;
+                    arrivals[next][p] += n;
+                }
+                Action::Spawn { amount, at } => {
+                    let cost = UNIT_COST.saturating_mul(amount.get());
+                    let Some(tile) = tiles.get(at) else { continue };
+                    if tile.owner == PLAYERS[p] && tile.passable() && matter[p] >= cost {
+                        arrivals[at][p] += amount.get();
+                        matter[p] -= cost;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    for (tile, arrived) in tiles.iter_mut().zip(arrivals.iter()) {
+        tile.units = [tile.units[0] + arrived[0], tile.units[1] + arrived[1]];
+    }
+}
+
+// The units left to each side once `mine` and `theirs` have met on one tile: they destroy each
+// other one for one. Only units ending the turn on the same tile fight, units swapping tiles
+// pass each other.
+pub fn fight(mine: i32, theirs: i32) -> (i32, i32) {
+    let lost = mine.min(theirs);
+    (mine - lost, theirs - lost)
+}
+
+// Every tile's fight once all units have landed, the survivors take the tile
+fn resolve_fights(tiles: &mut Grid<SimTile>) {
+    for tile in tiles.iter_mut() {
+        let (mine, theirs) = fight(tile.units[0], tile.units[1]);
+        tile.units = [mine, theirs];
+        if let Some(p) = (0..2).find(|&p| tile.units[p] > 0) {
+            tile.owner = PLAYERS[p];
+        }
+    }
+}
+
+// For each tile, whether each player's recyclers take scrap from it this turn: every recycler
+// reaches its own tile and the adjacent ones still holding scrap
+fn harvested_tiles(tiles: &Grid<SimTile>) -> Grid<[bool; 2]> {
+    let (width, height) = (tiles.width(), tiles.height());
+    let mut harvested = Grid::new(width, height, [false; 2]);
+    for pos in tiles.positions().filter(|&pos| tiles[pos].recycler) {
+        let Some(p) = PLAYERS.iter().position(|&owner| owner == tiles[pos].owner) else { continue };
+        for at in std::iter::once(pos).chain(pos.neighbors(width, height)) {
+            if tiles[at].scrap > 0 {
+                harvested[at][p] = true;
+            }
+        }
+    }
+    harvested
+}
+
+// A tile in range of several recyclers loses one scrap, but pays one matter to each of their owners
+fn harvest(tiles: &mut Grid<SimTile>, matter: &mut [i32; 2]) {
+    let harvested = harvested_tiles(tiles);
+    for (pos, by) in harvested.iter_with_pos() {
+        for p in 0..2 {
+            matter[p] += by[p] as i32;
+        }
+        if by.contains(&true) {
+            tiles[pos].scrap -= 1;
+        }
+    }
+}
+
+// Tiles out of scrap become neutral grass, the units and recycler on them are destroyed
+fn grass_over(tiles: &mut Grid<SimTile>) {
+    for tile in tiles.iter_mut().filter(|tile| tile.scrap == 0) {
+        *tile = SimTile { scrap: 0, owner: Owner::Neutral, recycler: false, units: [0; 2] };
+    }
+}
+
+fn pay_income(matter: &mut [i32; 2]) {
+    for income in matter.iter_mut() {
+        *income += BASE_INCOME;
+    }
+}
+
+impl GameState {
+    fn sim_tiles(&self) -> Grid<SimTile> {
+        Grid::from_fn(self.width(), self.height(), |pos| {
+            let loc = self.loc(pos);
+            let mut units = [0; 2];
+            if let Some(p) = PLAYERS.iter().position(|&owner| owner == loc.owner) {
+                units[p] = loc.units;
+            }
+            SimTile { scrap: loc.scrap_amount, owner: loc.owner, recycler: loc.recycler, units }
+        })
+    }
+
+    // The matter the player gets at the end of this turn if no recycler is built or lost:
+    // the base income plus the harvest of their recyclers
+    pub fn income(&self, owner: Owner) -> i32 {
+        let Some(p) = PLAYERS.iter().position(|&player| player == owner) else {
+            return 0;
+        };
+        let harvest = harvested_tiles(&self.sim_tiles()).iter().filter(|by| by[p]).count() as i32;
+        BASE_INCOME + harvest
+    }
+
+    // Where units ordered from `from` toward `to` stand after this turn's moves, None if they stay
+    pub fn engine_step_toward(&self, from: Pos, to: Pos) -> Option<Pos> {
+        engine_step(self.width(), self.height(), |pos| self.passable(pos), from, to)
+    }
+
+    pub fn apply(&self, actions: &ActionSets) -> GameState {
+        self.simulate(&actions.mine, &actions.enemy)
+    }
+
+    // The referee frame of the next turn: both players' matter and every tile's protocol fields
+    fn resolve_turn(&self, my_actions: &[Action], enemy_actions: &[Action]) -> ([i32; 2], Vec<[i32; 7]>) {
+        let mut tiles = self.sim_tiles();
+        let mut matter = [self.my_matter, self.enemy_matter];
+        let actions = [my_actions, enemy_actions];
+
+        build(&mut tiles, actions, &mut matter);
+        move_and_spawn(&mut tiles, actions, &mut matter);
+        resolve_fights(&mut tiles);
+        harvest(&mut tiles, &mut matter);
+        grass_over(&mut tiles);
+        pay_income(&mut matter);
+
+        let mut inputs: Vec<[i32; 7]> = tiles
+            .iter()
+            .map(|tile| [tile.scrap, tile.owner.to_code(), tile.units[0] + tile.units[1], tile.recycler as i32, 0, 0, 0])
+            .collect();
+        derive_flags(&mut inputs, self.width());
+        (matter, inputs)
+    }
+
+    // The next turn's board, with the turn counter and the change list advanced as if the
+    // referee had sent it
+    pub fn simulate(&self, my_actions: &[Action], enemy_actions: &[Action]) -> GameState {
+        let (matter, inputs) = self.resolve_turn(my_actions, enemy_actions);
+        let mut next = self.clone();
+        let result = next.update(matter[0], matter[1], &inputs);
+        debug_invariant!(result.is_ok(), "simulated tiles rejected: {:?}", result);
+        next
+    }
+
+    // Plays the turn in place, the same phases as simulate but only on the tiles they reach: the
+    // builds, the tiles units leave, land and fight on, the harvested tiles, and the flags around
+    // those. The Delta keeps each touched tile as it was and the state fields, for undo. simulate
+    // goes over the whole board instead, simcheck and the scenarios check both agree.
+    pub fn apply_delta(&mut self, actions: &ActionSets) -> Delta {
+        let mut delta = Delta {
+            tiles: Vec::new(),
+            turn: self.turn,
+            my_side: self.my_side,
+            my_matter: self.my_matter,
+            enemy_matter: self.enemy_matter,
+            my_robots: self.my_robots.clone(),
+            changed: self.changed.clone(),
+            hash: self.hash,
+        };
+        let mut matter = [self.my_matter, self.enemy_matter];
+        let actions = [actions.mine.as_slice(), actions.enemy.as_slice()];
+        for p in 0..2 {
+            for action in actions[p] {
+                let Action::Build { at } = *action else { continue };
+                if self.grid.get(at).is_some_and(|loc| loc.owner == PLAYERS[p] && loc.units == 0) && self.passable(at) && matter[p] >= RECYCLER_COST {
+                    delta.save(&self.grid, at);
+                    self.grid[at].recycler = true;
+                    matter[p] -= RECYCLER_COST;
+                }
+            }
+        }
+
+        // Units landing on each tile, added once every order has been read as in move_and_spawn
+        let mut arrivals: Vec<(Pos, [i32; 2])> = Vec::new();
+        let mut arrive = |pos: Pos, p: usize, n: i32| match arrivals.iter_mut().find(|(at, _)| *at == pos) {
+            Some((_, arrived)) => arrived[p] += n,
+            None => arrivals.push((pos, if p == 0 { [n, 0] } else { [0, n] })),
+        };
+        for p in 0..2 {
+            for action in actions[p] {
+                match *action {
+                    Action::Move { amount, from, to } => {
+                        if !self.grid.contains(from) || self.loc(from).owner != PLAYERS[p] {
+                            continue;
+                        }
+                        let n = amount.get().min(self.loc(from).units);
+                        let Some(next) = self.engine_step_toward(from, to).filter(|_| n > 0) else { continue };
+                        delta.save(&self.grid, from);
+                        self.grid[from].units -= n;
+                        arrive(next, p, n);
+                    }
+                    Action::Spawn { amount, at } => {
+                        let cost = UNIT_COST.saturating_mul(amount.get());
+                        if self.grid.get(at).is_some_and(|loc| loc.owner == PLAYERS[p]) && self.passable(at) && matter[p] >= cost {
+                            arrive(at, p, amount.get());
+                            matter[p] -= cost;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for (pos, arrived) in arrivals {
+            delta.save(&self.grid, pos);
+            let loc = &mut self.grid[pos];
+            let mut units = arrived;
+            if let Some(p) = PLAYERS.iter().position(|&owner| owner == loc.owner) {
+                units[p] += loc.units;
+            }
+            let (mine, theirs) = fight(units[0], units[1]);
+            loc.units = mine + theirs;
+            if let Some(p) = [mine, theirs].iter().position(|&n| n > 0) {
+                loc.owner = PLAYERS[p];
+            }
+        }
+
+        // Each tile a recycler reaches once, with the players harvesting it, as in harvested_tiles
+        let mut harvested: Vec<(Pos, [bool; 2])> = Vec::new();
+        for (pos, loc) in self.grid.iter_with_pos().filter(|(_, loc)| loc.recycler) {
+            let Some(p) = PLAYERS.iter().position(|&owner| owner == loc.owner) else { continue };
+            for at in std::iter::once(pos).chain(self.neighbors(pos)).filter(|&at| self.loc(at).scrap_amount > 0) {
+                match harvested.iter_mut().find(|(tile, _)| *tile == at) {
+                    Some((_, by)) => by[p] = true,
+                    None => harvested.push((at, if p == 0 { [true, false] } else { [false, true] })),
+                }
+            }
+        }
+        for (pos, by) in harvested {
+            for p in 0..2 {
+                matter[p] += by[p] as i32;
+            }
+            delta.save(&self.grid, pos);
+            let loc = &mut self.grid[pos];
+            loc.scrap_amount -= 1;
+            if loc.scrap_amount == 0 {
+                *loc = Location { scrap_amount: 0, owner: Owner::Neutral, units: 0, recycler: false, ..loc.clone() };
+            }
+        }
+        pay_income(&mut matter);
+
+        // The referee's flags, on the touched tiles and on their neighbors for recycler range
+        let touched: Vec<Pos> = delta.tiles.iter().map(|&(pos, _)| pos).collect();
+        for &at in touched.iter() {
+            for pos in std::iter::once(at).chain(self.neighbors(at)) {
+                let loc = self.loc(pos);
+                let mine = loc.owner == Owner::Me && !loc.recycler && loc.scrap_amount > 0;
+                let in_range = loc.scrap_amount > 0 && std::iter::once(pos).chain(self.neighbors(pos)).any(|next| self.loc(next).recycler);
+                let flags = (mine && loc.units == 0, mine, in_range);
+                if flags != (loc.can_build, loc.can_spawn, loc.in_range_of_recycler) {
+                    delta.save(&self.grid, pos);
+                    let loc = &mut self.grid[pos];
+                    (loc.can_build, loc.can_spawn, loc.in_range_of_recycler) = flags;
+                }
+            }
+        }
+
+        // What update would have set, from the touched tiles alone
+        let width = self.width();
+        delta.tiles.sort_by_key(|(pos, _)| pos.index(width));
+        let fields = |loc: &Location| (loc.scrap_amount, loc.owner, loc.units, loc.recycler);
+        self.changed.clear();
+        self.hash ^= zobrist::matter_key(self.my_matter, self.enemy_matter) ^ zobrist::matter_key(matter[0], matter[1]);
+        for (pos, before) in delta.tiles.iter() {
+            let loc = &self.grid[*pos];
+            if fields(before) != fields(loc) {
+                self.changed.push(*pos);
+                self.hash ^= zobrist::tile_key(pos.index(width), before) ^ zobrist::tile_key(pos.index(width), loc);
+            }
+        }
+        let is_robot = |loc: &Location| loc.owner == Owner::Me && loc.units > 0;
+        self.my_robots.retain(|&pos| is_robot(&self.grid[pos]));
+        for &(pos, _) in delta.tiles.iter() {
+            if is_robot(&self.grid[pos]) && !self.my_robots.contains(&pos) {
+                self.my_robots.push(pos);
+            }
+        }
+        self.my_robots.sort_by_key(|pos| pos.index(width));
+        self.turn += 1;
+        (self.my_matter, self.enemy_matter) = (matter[0], matter[1]);
+        if self.my_side.is_none() {
+            self.my_side = self.detect_side();
+        }
+        delta
+    }
+
+    pub fn undo(&mut self, delta: Delta) {
+        for (pos, loc) in delta.tiles {
+            self.grid[pos] = loc;
+        }
+        self.turn = delta.turn;
+        self.my_side = delta.my_side;
+        self.my_matter = delta.my_matter;
+        self.enemy_matter = delta.enemy_matter;
+        self.my_robots = delta.my_robots;
+        self.changed = delta.changed;
+        self.hash = delta.hash;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::parse_actions;
+
+    // A build, a spawn, moves meeting in a fight, and a tile harvested down to grass. The
+    // middle column is out of reach of all of it.
+    const BOARD: &str = "
+        5m2  5m   3.   5.   5.   5.   5.   5e1
+        5M   1m1  5.   5e1  5.   5.   5.   5e3
+        1.   5m   5.   5.   5.   5.   5E   5e
+    ";
+    const MINE: &str = "BUILD 1 0;MOVE 1 1 1 2 1;MOVE 2 0 0 0 0;SPAWN 1 1 2";
+    const ENEMY: &str = "MOVE 1 3 1 2 1;MOVE 3 7 1 6 1;MOVE 1 7 0 6 0";
+
+    fn board() -> GameState {
+        let mut game = GameState::from_ascii(BOARD).unwrap();
+        (game.my_matter, game.enemy_matter) = (20, 20);
+        game.hash = zobrist::full_hash(&game);
+        game
+    }
+
+    fn action_sets(mine: &str, enemy: &str) -> ActionSets {
+        ActionSets { mine: parse_actions(mine).unwrap(), enemy: parse_actions(enemy).unwrap() }
+    }
+
+    #[test]
+    fn apply_then_undo_round_trips() {
+        let before = board();
+        let actions = action_sets(MINE, ENEMY);
+        let mut game = before.clone();
+        let delta = game.apply_delta(&actions);
+        assert_eq!(game, before.simulate(&actions.mine, &actions.enemy));
+        assert_eq!(game.hash(), zobrist::full_hash(&game));
+        game.undo(delta);
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn only_touched_tiles_are_recorded() {
+        let before = board();
+        let mut game = before.clone();
+        let delta = game.apply_delta(&action_sets(MINE, ENEMY));
+        let recorded: Vec<Pos> = delta.tiles().map(|(pos, _)| pos).collect();
+        for pos in game.positions().filter(|pos| !recorded.contains(pos)) {
+            assert_eq!(game.loc(pos), before.loc(pos), "{:?} changed without being recorded", pos);
+        }
+        for (pos, loc) in delta.tiles() {
+            assert_eq!(loc, before.loc(pos), "{:?} recorded after it changed", pos);
+        }
+        assert!(recorded.iter().all(|pos| pos.x() != 4), "{:?}", recorded);
+    }
+
+    #[test]
+    fn turns_undo_in_reverse_order() {
+        let start = board();
+        let mut game = start.clone();
+        let first = game.apply_delta(&action_sets(MINE, ENEMY));
+        let after_first = game.clone();
+        let second = game.apply_delta(&action_sets("MOVE 1 2 1 2 0;SPAWN 2 0 0", "SPAWN 1 7 2"));
+        assert_eq!(game, after_first.simulate(&parse_actions("MOVE 1 2 1 2 0;SPAWN 2 0 0").unwrap(), &parse_actions("SPAWN 1 7 2").unwrap()));
+        game.undo(second);
+        assert_eq!(game, after_first);
+        game.undo(first);
+        assert_eq!(game, start);
+    }
+
+    #[test]
+    fn waiting_touches_only_the_harvest() {
+        let before = board();
+        let mut game = before.clone();
+        let delta = game.apply_delta(&ActionSets::default());
+        assert_eq!(game, before.simulate(&[], &[]));
+        // Both recyclers and the scrap tiles next to them
+        let mut recorded: Vec<(usize, usize)> = delta.tiles().map(|(pos, _)| (pos.x(), pos.y())).collect();
+        recorded.sort();
+        assert_eq!(recorded, vec![(0, 0), (0, 1), (0, 2), (1, 1), (5, 2), (6, 1), (6, 2), (7, 2)]);
+    }
+}
@@ -0,0 +1,1347 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::actions::{Action, Amount};
+use crate::analysis::Analysis;
+use crate::budget::{MatterBudget, RECYCLER_COST, UNIT_COST};
+use crate::config_default::DEFAULT_CONFIG;
+use crate::game::{bfs, GameState, Owner, Side, UNREACHABLE};
+use crate::grid::Grid;
+use crate::health::{self, BotError};
+use crate::pos::{Direction, Pos};
+use crate::rng::{splitmix64, Rng};
+use crate::search;
+use crate::transposition::TranspositionTable;
+
+const SIEGE_MIN_TURN: usize = 100;
+const SIEGE_UNIT_RATIO: i32 = 2;
+const SIEGE_RING_RADIUS: usize = 2;
+const SPAWN_MIN_TILE_LIFE: i32 = 3;
+const ENEMY_PASSIVE_TURNS: usize = 3;
+const INFILTRATOR_MAX_TILES: usize = 2;
+const DENIAL_MIN_SCORE: i32 = 2;
+const SIEGE_RECYCLER_MIN_SCORE: i32 = 0;
+const PLAN_COOLDOWN_TURNS: usize = 5;
+const PLAN_ZONE_RADIUS: usize = 2;
+const THREAT_GROUP_RADIUS: usize = 2;
+const THREAT_TRACK_RADIUS: f64 = 3.;
+const THREAT_LOOKAHEAD_TURNS: f64 = 2.;
+const DEFENSE_CALM_TURNS: usize = 5;
+const WALL_MAX_TILES: usize = 2;
+const WALL_MAX_TURN: usize = 20;
+// Trades looked ahead each turn, the lookahead plays every one against every reply
+const SACRIFICE_MAX_CANDIDATES: usize = 8;
+// Boards the lookahead evaluates in a turn: a line per trade and the planned one, times the replies
+const SEARCH_TABLE_CAPACITY: usize = 1024;
+const STRATEGY_NAME: &str = "greedy-outside";
+// Set by build.rs, missing when the file is pasted into the CodinGame IDE
+const BUILD_TIMESTAMP: Option<&str> = option_env!("BUILD_TIMESTAMP");
+const MESSAGE_MAX_LEN: usize = 40;
+
+// Weights of the directional features scoring an expansion step, only the ratios matter
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalPrior {
+    pub toward_enemy: f64,
+    pub toward_center: f64,
+    pub along_rows: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub expansion_prior: DirectionalPrior,
+    // Bounds of the extra units held against adjacent enemies, adapted in between during the game
+    pub defense_margin_range: (i32, i32),
+    // Fixes every random choice of a game, None seeds from the clock
+    pub seed: Option<u64>,
+    // Chance that a stack spreads over all its neighbors in a random order instead of the planned
+    // ones, so self-play games explore other lines. 0 on the ladder, where it costs games.
+    pub exploration: f64,
+    // Milliseconds into planning after which the skippable analyses are not started, see Analysis.
+    // None never skips them, so seeded games replay exactly.
+    pub analysis_budget_ms: Option<u128>,
+}
+
+// The best Config the tuner found, see config_default.rs
+impl Default for Config {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+impl Config {
+    // The same for the same parameters on every build and platform, shown in the first turn's
+    // message so a ladder replay tells which parameters played it. The seed is left out, it
+    // changes every game and the bot logs it on its own, and so is the analysis budget, which
+    // only matters on a turn about to time out.
+    pub fn fingerprint(&self) -> u32 {
+        let Config { expansion_prior, defense_margin_range, seed: _, exploration, analysis_budget_ms: _ } = *self;
+        let DirectionalPrior { toward_enemy, toward_center, along_rows } = expansion_prior;
+        let fields = [
+            toward_enemy.to_bits(),
+            toward_center.to_bits(),
+            along_rows.to_bits(),
+            defense_margin_range.0 as u64,
+            defense_margin_range.1 as u64,
+            exploration.to_bits(),
+        ];
+        (fields.iter().fold(0, |hash, &field| splitmix64(hash ^ field)) >> 32) as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanKind {
+    Siege,
+    Wall,
+}
+
+#[derive(Debug)]
+struct FailedPlan {
+    kind: PlanKind,
+    at: Pos,
+    reason: &'static str,
+    turn: usize,
+}
+
+// Units spawned behind a saturated frontier this turn, to step onto it next turn
+#[derive(Debug, Clone, Copy)]
+struct Commitment {
+    from: Pos,
+    to: Pos,
+    units: i32,
+    turn: usize,
+}
+
+#[derive(Debug)]
+struct ThreatGroup {
+    tiles: Vec<Pos>,
+    units: i32,
+    centroid: (f64, f64),
+    velocity: (f64, f64),
+}
+
+impl ThreatGroup {
+    // Where the group ends up if it keeps its current heading for a few turns
+    fn predicted_target(&self, width: usize, height: usize) -> Pos {
+        let project = |c: f64, v: f64, size: usize| (c + v * THREAT_LOOKAHEAD_TURNS).round().clamp(0., (size - 1) as f64) as usize;
+        Pos::new(project(self.centroid.0, self.velocity.0, width), project(self.centroid.1, self.velocity.1, height))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Role {
+    Scout,
+    Stack,
+}
+
+impl Role {
+    fn from_units(units: i32) -> Self {
+        if units == 1 { Role::Scout } else { Role::Stack }
+    }
+}
+
+// A bot's whole decision for a turn, fed each board the referee sends in order
+pub trait Strategy {
+    fn plan(&mut self, state: &GameState) -> Vec<Action>;
+}
+
+// What the planner phases share while building one turn's actions
+struct TurnContext<'a> {
+    game: &'a GameState,
+    analysis: &'a Analysis<'a>,
+    actions: Vec<Action>,
+    // Units planned to arrive on each tile, by moves and spawns
+    incoming: Grid<i32>,
+    // Units already given an order on each tile
+    committed: Grid<i32>,
+    budget: MatterBudget,
+    built: Vec<Pos>,
+    // Orders for next turn, kept by the strategy once the turn is planned
+    commitments: Vec<Commitment>,
+    rng: Rng,
+}
+
+type Phase = fn(&GreedyOutsideStrategy, &mut TurnContext);
+
+// Builds one turn's actions by running its phases in order on a shared context,
+// each phase seeing what the earlier ones planned
+struct Planner;
+
+impl Planner {
+    const PHASES: [Phase; 10] = [
+        GreedyOutsideStrategy::evacuate,
+        GreedyOutsideStrategy::hunt_infiltrators,
+        GreedyOutsideStrategy::build_wall,
+        GreedyOutsideStrategy::build_recyclers,
+        GreedyOutsideStrategy::honour_commitments,
+        GreedyOutsideStrategy::move_robots,
+        GreedyOutsideStrategy::sacrifice,
+        // Spawns go where the moves left room on the front
+        GreedyOutsideStrategy::spawn_robots,
+        GreedyOutsideStrategy::validate,
+        GreedyOutsideStrategy::add_message,
+    ];
+
+    fn run(strategy: &GreedyOutsideStrategy, game: &GameState, analysis: &Analysis, rng: Rng) -> (Vec<Action>, Vec<Commitment>) {
+        let mut ctx = TurnContext {
+            game,
+            analysis,
+            actions: Vec::new(),
+            incoming: Grid::new(game.width(), game.height(), 0),
+            committed: Grid::new(game.width(), game.height(), 0),
+            budget: MatterBudget::new(game.my_matter),
+            built: Vec::new(),
+            commitments: Vec::new(),
+            rng,
+        };
+        for phase in Self::PHASES {
+            phase(strategy, &mut ctx);
+        }
+        (ctx.actions, ctx.commitments)
+    }
+}
+
+// Expands toward the closest tiles not owned yet, with the analyses it keeps from turn to turn
+pub struct GreedyOutsideStrategy {
+    config: Config,
+    grid_dist_to_outside: Grid<u16>,
+    grid_danger: Grid<i32>,
+    threat_groups: Vec<ThreatGroup>,
+    siege_target: Option<Pos>,
+    // Tiles around the siege target the stacks close in on, and where siege recyclers go up
+    siege_ring: Vec<Pos>,
+    // Corridor tiles still to wall off in the opening
+    wall: Vec<Pos>,
+    failed_plans: Vec<FailedPlan>,
+    grid_dist_to_siege: Grid<u16>,
+    bfs_queue: VecDeque<Pos>,
+    enemy_snapshot: Vec<(Pos, i32, bool)>,
+    enemy_passive_turns: usize,
+    predicted_flips: Vec<Pos>,
+    flips_predicted_total: usize,
+    flips_happened_total: usize,
+    predicted_holds: Vec<Pos>,
+    defense_margin: i32,
+    calm_turns: usize,
+    // What the last turn's spawns behind the frontier were sent there for
+    commitments: Vec<Commitment>,
+    rng: Rng,
+    // Evaluations of the boards the lookahead reached, the phases only borrow the strategy
+    search_table: RefCell<TranspositionTable>,
+}
+
+impl GreedyOutsideStrategy {
+    pub fn new(game: &GameState, config: Config) -> Self {
+        let (width, height) = (game.width(), game.height());
+        GreedyOutsideStrategy {
+            config,
+            grid_dist_to_outside: Grid::new(width, height, UNREACHABLE),
+            grid_danger: Grid::new(width, height, 0),
+            threat_groups: Vec::new(),
+            siege_target: None,
+            siege_ring: Vec::new(),
+            wall: Vec::new(),
+            failed_plans: Vec::new(),
+            grid_dist_to_siege: Grid::new(width, height, UNREACHABLE),
+            bfs_queue: VecDeque::with_capacity(width * height),
+            enemy_snapshot: Vec::new(),
+            enemy_passive_turns: 0,
+            predicted_flips: Vec::new(),
+            flips_predicted_total: 0,
+            flips_happened_total: 0,
+            predicted_holds: Vec::new(),
+            defense_margin: config.defense_margin_range.0,
+            calm_turns: 0,
+            commitments: Vec::new(),
+            rng: config.seed.map_or_else(Rng::from_clock, Rng::new),
+            search_table: RefCell::new(TranspositionTable::new(SEARCH_TABLE_CAPACITY)),
+        }
+    }
+
+    // Starts the game on the margin earlier games against the same opponent ended on
+    pub fn seed_defense_margin(&mut self, margin: i32) {
+        let (min_margin, max_margin) = self.config.defense_margin_range;
+        self.defense_margin = margin.clamp(min_margin, max_margin);
+    }
+
+    pub fn defense_margin(&self) -> i32 {
+        self.defense_margin
+    }
+
+    // Refreshes every analysis from the board the referee just sent
+    fn update(&mut self, game: &GameState, analysis: &Analysis) {
+        let mut outside_coords: Vec<Pos> = game.iter_cells()
+            .filter(|(_, loc)| loc.owner != Owner::Me && loc.scrap_amount > 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        self.grid_danger.clone_from(analysis.threat());
+        // Uncontested dead ends can be taken any time, expansion heads for the rest of the map first
+        let dead_ends = analysis.dead_ends();
+        let contested_coords: Vec<Pos> = outside_coords
+            .iter()
+            .copied()
+            .filter(|&pos| !dead_ends[pos] || self.grid_danger[pos] > 0)
+            .collect();
+        if !contested_coords.is_empty() {
+            outside_coords = contested_coords;
+        }
+        bfs(&outside_coords, &mut self.bfs_queue, &mut self.grid_dist_to_outside);
+        self.update_threat_groups(game);
+        self.update_enemy_passivity(game);
+        self.update_siege(game);
+        self.update_wall(game, analysis);
+        self.check_predicted_flips(game);
+        self.adapt_defense_margin(game);
+
+        // Per-turn dumps stay out of the submission, whose stderr CodinGame cuts short
+        #[cfg(feature = "instrumentation")]
+        eprintln!("{}", self.grid_dist_to_outside.rows().map(|row| row.iter().map(|val| val.to_string()).collect::<Vec<String>>().join(" ")).collect::<Vec<String>>().join("\n"));
+    }
+
+    // How well a step from one tile to a neighbor follows the configured expansion geometry
+    fn expansion_prior(&self, game: &GameState, from: Pos, to: Pos) -> f64 {
+        let prior = self.config.expansion_prior;
+        let (cx, cy) = ((game.width() - 1) as f64 / 2., (game.height() - 1) as f64 / 2.);
+        let dist_to_center = |pos: Pos| (pos.x() as f64 - cx).abs() + (pos.y() as f64 - cy).abs();
+        let toward_enemy = game.normalized_column(to.x()) as f64 - game.normalized_column(from.x()) as f64;
+        let toward_center = dist_to_center(from) - dist_to_center(to);
+        let along_rows = if from.direction_to(to).is_some_and(Direction::is_horizontal) { 1. } else { 0. };
+        prior.toward_enemy * toward_enemy + prior.toward_center * toward_center + prior.along_rows * along_rows
+    }
+
+    // Densest enemy-owned tile, ties broken by distance from my territory
+    fn enemy_core(&mut self, game: &GameState) -> Option<Pos> {
+        let my_tiles: Vec<Pos> = game.iter_owned(Owner::Me).map(|(pos, _)| pos).collect();
+        let mut dist_to_me = Grid::new(game.width(), game.height(), UNREACHABLE);
+        bfs(&my_tiles, &mut self.bfs_queue, &mut dist_to_me);
+        let enemy_tiles: Vec<Pos> = game.iter_owned(Owner::Enemy).map(|(pos, _)| pos).collect();
+        enemy_tiles
+            .iter()
+            .copied()
+            .max_by_key(|&pos| {
+                let density = enemy_tiles
+                    .iter()
+                    .filter(|&&other| pos.manhattan(other) <= SIEGE_RING_RADIUS)
+                    .count();
+                (density, dist_to_me[pos])
+            })
+    }
+
+    // The enemy is passive while its units, spawns and recyclers stay exactly as they were
+    fn update_enemy_passivity(&mut self, game: &GameState) {
+        let snapshot: Vec<(Pos, i32, bool)> = game.iter_owned(Owner::Enemy)
+            .filter(|(_, loc)| loc.units > 0 || loc.recycler)
+            .map(|(pos, loc)| (pos, loc.units, loc.recycler))
+            .collect();
+        let was_greedy = self.greedy_mode();
+        if !self.enemy_snapshot.is_empty() && snapshot == self.enemy_snapshot {
+            self.enemy_passive_turns += 1;
+        }
+        else {
+            self.enemy_passive_turns = 0;
+        }
+        self.enemy_snapshot = snapshot;
+        if self.greedy_mode() != was_greedy {
+            eprintln!("GREEDY MODE: {} after {} passive enemy turns", self.greedy_mode(), self.enemy_passive_turns);
+        }
+    }
+
+    // Nothing to defend against, every unit just grabs the closest free tiles
+    fn greedy_mode(&self) -> bool {
+        self.enemy_passive_turns >= ENEMY_PASSIVE_TURNS
+    }
+
+    fn record_failed_plan(&mut self, turn: usize, kind: PlanKind, at: Pos, reason: &'static str) {
+        eprintln!("PLAN ABANDONED: {:?} at {:?}, {}", kind, at, reason);
+        self.failed_plans.retain(|plan| turn - plan.turn < PLAN_COOLDOWN_TURNS);
+        self.failed_plans.push(FailedPlan { kind, at, reason, turn });
+    }
+
+    // A plan abandoned recently in the same zone is not proposed again until the cooldown ends
+    fn plan_on_cooldown(&self, turn: usize, kind: PlanKind, at: Pos) -> bool {
+        let blocking = self.failed_plans.iter().find(|plan| {
+            plan.kind == kind && plan.at.manhattan(at) <= PLAN_ZONE_RADIUS && turn - plan.turn < PLAN_COOLDOWN_TURNS
+        });
+        if let Some(plan) = blocking {
+            eprintln!("PLAN ON COOLDOWN: {:?} at {:?}, abandoned turn {} ({})", kind, at, plan.turn, plan.reason);
+        }
+        blocking.is_some()
+    }
+
+    // Late game with a clear unit lead: stacks stop expanding and ring the enemy core instead
+    fn update_siege(&mut self, game: &GameState) {
+        let previous = self.siege_target;
+        let winning = game.count_units(Owner::Me) >= SIEGE_UNIT_RATIO * game.count_units(Owner::Enemy);
+        let broken = if game.turn < SIEGE_MIN_TURN {
+            Some("too early")
+        }
+        else if !winning {
+            Some("unit lead lost")
+        }
+        else if self.greedy_mode() {
+            Some("enemy passive")
+        }
+        else {
+            None
+        };
+        self.siege_target = match broken {
+            Some(_) => None,
+            None => self.enemy_core(game).filter(|&core| !self.plan_on_cooldown(game.turn, PlanKind::Siege, core)),
+        };
+        if let (Some(core), None, Some(reason)) = (previous, self.siege_target, broken) {
+            self.record_failed_plan(game.turn, PlanKind::Siege, core, reason);
+        }
+        self.siege_ring.clear();
+        if let Some(core) = self.siege_target {
+            self.siege_ring = game.positions()
+                .filter(|&pos| pos.manhattan(core) == SIEGE_RING_RADIUS && game.passable(pos))
+                .collect();
+            eprintln!("SIEGE: core {:?}, ring {:?}", core, self.siege_ring);
+            bfs(&self.siege_ring, &mut self.bfs_queue, &mut self.grid_dist_to_siege);
+        }
+    }
+
+    // On a corridor map, recyclers on the corridor cut the map in two and the game becomes a
+    // farming race my side wins. The plan is made on the first turn and dropped once the enemy
+    // holds one of the tiles or the opening is over.
+    fn update_wall(&mut self, game: &GameState, analysis: &Analysis) {
+        if game.turn == 1 {
+            self.wall = corridor_wall(game, analysis.regions());
+            if !self.wall.is_empty() {
+                eprintln!("WALL: corridor {:?}", self.wall);
+            }
+        }
+        self.wall.retain(|&pos| game.passable(pos));
+        let broken = if self.wall.iter().any(|&pos| game.loc(pos).owner == Owner::Enemy) {
+            Some("enemy in the corridor")
+        }
+        else if game.turn > WALL_MAX_TURN {
+            Some("opening over")
+        }
+        else {
+            None
+        };
+        if let (Some(&at), Some(reason)) = (self.wall.first(), broken) {
+            self.record_failed_plan(game.turn, PlanKind::Wall, at, reason);
+            self.wall.clear();
+        }
+    }
+
+    // Tiles not owned by me that my moves step onto this turn are expected to be mine next turn,
+    // and so are my tiles next to enemy units that keep at least as many units as they face
+    fn predict_next_turn(&mut self, game: &GameState, actions: &[Action]) {
+        self.predicted_flips.clear();
+        for action in actions {
+            if let Action::Move { from, to, .. } = *action {
+                let Some(next) = game.engine_step_toward(from, to) else { continue };
+                if game.loc(next).owner != Owner::Me && !self.predicted_flips.contains(&next) {
+                    self.predicted_flips.push(next);
+                }
+            }
+        }
+        let (outgoing, incoming) = unit_flows(game, actions);
+        self.predicted_holds = game.iter_owned(Owner::Me)
+            .map(|(pos, loc)| (pos, adjacent_enemy_units(game, pos), loc.units - outgoing[pos] + incoming[pos]))
+            .filter(|&(_, attackers, defenders)| attackers > 0 && defenders >= attackers)
+            .map(|(pos, _, _)| pos)
+            .collect();
+    }
+
+    // Losing a tile I expected to hold means the enemy brings more than it shows, so the margin
+    // grows; after a few turns without such a loss it shrinks back
+    fn adapt_defense_margin(&mut self, game: &GameState) {
+        if self.predicted_holds.is_empty() {
+            return;
+        }
+        let lost = self.predicted_holds.iter().filter(|&&pos| game.loc(pos).owner != Owner::Me).count();
+        let (min_margin, max_margin) = self.config.defense_margin_range;
+        let previous = self.defense_margin;
+        if lost > 0 {
+            self.calm_turns = 0;
+            self.defense_margin = (self.defense_margin + 1).min(max_margin);
+        }
+        else {
+            self.calm_turns += 1;
+            if self.calm_turns >= DEFENSE_CALM_TURNS {
+                self.calm_turns = 0;
+                self.defense_margin = (self.defense_margin - 1).max(min_margin);
+            }
+        }
+        if self.defense_margin != previous {
+            eprintln!(
+                "DEFENSE MARGIN: {} -> {}, lost {} of {} tiles expected to hold",
+                previous, self.defense_margin, lost, self.predicted_holds.len()
+            );
+        }
+    }
+
+    fn check_predicted_flips(&mut self, game: &GameState) {
+        if self.predicted_flips.is_empty() {
+            return;
+        }
+        let happened = self.predicted_flips
+            .iter()
+            .filter(|&&pos| game.loc(pos).owner == Owner::Me)
+            .count();
+        self.flips_predicted_total += self.predicted_flips.len();
+        self.flips_happened_total += happened;
+        eprintln!(
+            "FLIPS: {}/{} this turn, {}/{} this game ({:.1}%)",
+            happened,
+            self.predicted_flips.len(),
+            self.flips_happened_total,
+            self.flips_predicted_total,
+            100. * self.flips_happened_total as f64 / self.flips_predicted_total as f64,
+        );
+    }
+
+    // Enemy stacks within THREAT_GROUP_RADIUS of each other form a group, matched to last turn's
+    // nearest group to get its heading; the area each group is heading for becomes dangerous too
+    fn update_threat_groups(&mut self, game: &GameState) {
+        let stacks: Vec<Pos> = game.iter_owned(Owner::Enemy)
+            .filter(|(_, loc)| loc.units > 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        let mut assigned = vec![false; stacks.len()];
+        let mut groups = Vec::new();
+        for start in 0..stacks.len() {
+            if assigned[start] {
+                continue;
+            }
+            assigned[start] = true;
+            let mut members = vec![start];
+            let mut k = 0;
+            while k < members.len() {
+                let pos = stacks[members[k]];
+                for other in 0..stacks.len() {
+                    if !assigned[other] && pos.manhattan(stacks[other]) <= THREAT_GROUP_RADIUS {
+                        assigned[other] = true;
+                        members.push(other);
+                    }
+                }
+                k += 1;
+            }
+            let tiles: Vec<Pos> = members.into_iter().map(|m| stacks[m]).collect();
+            let units: i32 = tiles.iter().map(|&pos| game.loc(pos).units).sum();
+            let (sum_x, sum_y) = tiles.iter().fold((0., 0.), |(sx, sy), &pos| {
+                let w = game.loc(pos).units as f64;
+                (sx + w * pos.x() as f64, sy + w * pos.y() as f64)
+            });
+            let centroid = (sum_x / units as f64, sum_y / units as f64);
+            let velocity = self.threat_groups
+                .iter()
+                .map(|previous| (previous.centroid, (centroid.0 - previous.centroid.0).hypot(centroid.1 - previous.centroid.1)))
+                .filter(|&(_, d)| d <= THREAT_TRACK_RADIUS)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(previous, _)| (centroid.0 - previous.0, centroid.1 - previous.1))
+                .unwrap_or((0., 0.));
+            groups.push(ThreatGroup { tiles, units, centroid, velocity });
+        }
+        for group in groups.iter() {
+            let target = group.predicted_target(game.width(), game.height());
+            eprintln!(
+                "THREAT GROUP: {} units on {:?}, centroid ({:.1}, {:.1}), velocity ({:.1}, {:.1}), target {:?}",
+                group.units, group.tiles, group.centroid.0, group.centroid.1, group.velocity.0, group.velocity.1, target
+            );
+            if group.velocity != (0., 0.) {
+                self.grid_danger[target] += group.units;
+            }
+        }
+        self.threat_groups = groups;
+    }
+
+    // Scouts pay for every enemy unit able to reach the destination, stacks only care about the distance
+    fn path_cost(&self, role: &Role, pos: Pos) -> i32 {
+        if self.greedy_mode() {
+            return self.grid_dist_to_outside[pos] as i32;
+        }
+        match role {
+            Role::Scout => self.grid_dist_to_outside[pos] as i32 + self.grid_danger[pos],
+            Role::Stack if self.siege_target.is_some() && self.grid_dist_to_siege[pos] != UNREACHABLE =>
+                self.grid_dist_to_siege[pos] as i32,
+            Role::Stack => self.grid_dist_to_outside[pos] as i32,
+        }
+    }
+
+    // Units left on a tile that turns to grass this turn are destroyed, they move before anyone else
+    fn evacuate(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        let dying = |pos: Pos| game.turns_to_live(pos).is_some_and(|turns| turns <= 1);
+        for (from, loc) in game.iter_my_robots().filter(|&(pos, _)| dying(pos)) {
+            let role = Role::from_units(loc.units);
+            let escape = game.neighbors(from)
+                .filter(|&to| game.passable(to) && !dying(to))
+                .min_by_key(|&to| self.path_cost(&role, to));
+            let Some(to) = escape else {
+                health::report(BotError::Planner(format!("no escape for {:?}", from)));
+                continue;
+            };
+            let Some(amount) = Amount::new(game.loc(from).units) else {
+                continue;
+            };
+            ctx.committed[from] += amount.get();
+            ctx.incoming[to] += amount.get();
+            ctx.actions.push(Action::Move { amount, from, to });
+        }
+    }
+
+    // Adjacent stacks strike when they outnumber the intruder, otherwise they hold and get reinforced
+    fn hunt_infiltrators(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        for target in infiltrators(game) {
+            let needed = game.loc(target).units + 1;
+            let mut hunters = Vec::new();
+            let mut gathered = 0;
+            for from in game.neighbors(target) {
+                let free = if game.loc(from).owner == Owner::Me { game.loc(from).units - ctx.committed[from] } else { 0 };
+                let take = free.min(needed - gathered);
+                if take > 0 {
+                    hunters.push((from, take));
+                    gathered += take;
+                }
+            }
+            for &(from, take) in hunters.iter() {
+                ctx.committed[from] += take;
+                if let Some(amount) = Amount::new(take).filter(|_| gathered >= needed) {
+                    ctx.actions.push(Action::Move { amount, from, to: target });
+                }
+            }
+            let reinforcement_tile = game.neighbors(target).find(|&pos| game.loc(pos).can_spawn);
+            if let Some(at) = reinforcement_tile.filter(|_| gathered < needed) {
+                if let Some(amount) = Amount::new((needed - gathered).min(ctx.budget.affordable_units())) {
+                    if ctx.budget.try_reserve(UNIT_COST * amount.get()) {
+                        ctx.incoming[at] += amount.get();
+                        ctx.actions.push(Action::Spawn { amount, at });
+                    }
+                }
+            }
+            eprintln!("INFILTRATOR: {:?}, units: {}, hunters: {:?}", target, game.loc(target).units, hunters);
+        }
+    }
+
+    // Walls off the corridor tiles I can build on, and saves the matter for the others. The
+    // nearest free unit steps toward each tile not mine yet.
+    fn build_wall(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        for &at in self.wall.iter() {
+            let runner = game
+                .iter_my_robots()
+                .filter(|&(from, loc)| game.loc(at).owner != Owner::Me && loc.units > ctx.committed[from])
+                .flat_map(|(from, _)| game.neighbors(from).map(move |to| (from, to)))
+                .filter(|&(from, to)| game.passable(to) && !ctx.built.contains(&to) && to.manhattan(at) < from.manhattan(at))
+                .min_by_key(|&(from, _)| from.manhattan(at));
+            if let Some((from, to)) = runner {
+                eprintln!("WALL RUNNER: {:?} -> {:?} for {:?}", from, to, at);
+                ctx.committed[from] += 1;
+                ctx.incoming[to] += 1;
+                ctx.actions.push(Action::Move { amount: Amount::ONE, from, to });
+            }
+            if game.loc(at).can_build && ctx.committed[at] == 0 && ctx.incoming[at] == 0 && ctx.budget.try_reserve(RECYCLER_COST) {
+                eprintln!("WALL RECYCLER: {:?}", at);
+                ctx.built.push(at);
+                ctx.actions.push(Action::Build { at });
+            }
+            else {
+                ctx.budget.save(RECYCLER_COST);
+            }
+        }
+    }
+
+    fn build_recyclers(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        // Not where units are sent or spawned this turn: the recycler would go up first and block them
+        let buildable = |pos: Pos| game.loc(pos).can_build && ctx.committed[pos] == 0 && ctx.incoming[pos] == 0;
+        // A tile the enemy takes next turn whatever I do is worth more as a recycler in their face
+        let lost_tile = game.positions()
+            .filter(|&pos| buildable(pos))
+            .filter(|&pos| {
+                let (attackers, defenders) = game.neighbors(pos)
+                    .fold((0, 0), |(attackers, defenders), next| match game.loc(next).owner {
+                        Owner::Enemy => (attackers + game.loc(next).units, defenders),
+                        Owner::Me => (attackers, defenders + game.loc(next).units - ctx.committed[next]),
+                        Owner::Neutral => (attackers, defenders),
+                    });
+                attackers > 0 && attackers + self.defense_margin > defenders + ctx.budget.affordable_units()
+            })
+            .max_by_key(|&pos| (denial_score(game, pos), game.loc(pos).scrap_amount));
+        if let Some(at) = lost_tile.filter(|_| ctx.budget.try_reserve(RECYCLER_COST)) {
+            eprintln!("LAST-DITCH RECYCLER: {:?}", at);
+            ctx.built.push(at);
+            ctx.actions.push(Action::Build { at });
+        }
+        // One area-denial recycler per turn, on my tile facing the enemy that burns the most of their ground
+        let denial_target = game.positions()
+            .filter(|&pos| buildable(pos) && !ctx.built.contains(&pos))
+            .filter(|&pos| game.neighbors(pos).any(|next| game.loc(next).owner == Owner::Enemy))
+            .map(|pos| (denial_score(game, pos), pos))
+            .filter(|&(score, _)| score >= DENIAL_MIN_SCORE)
+            .max_by_key(|&(score, pos)| (score, pos.row_col()));
+        if let Some((score, at)) = denial_target.filter(|_| ctx.budget.try_reserve(RECYCLER_COST)) {
+            eprintln!("DENIAL RECYCLER: {:?}, score: {}", at, score);
+            ctx.built.push(at);
+            ctx.actions.push(Action::Build { at });
+        }
+        // During a siege, one recycler per turn closes a ring tile of mine the enemy could break out
+        // through, where it burns at least as much of their ground as of mine
+        let siege_target = self.siege_ring
+            .iter()
+            .copied()
+            .filter(|&pos| buildable(pos) && !ctx.built.contains(&pos))
+            .filter(|&pos| game.neighbors(pos).any(|next| game.loc(next).owner == Owner::Enemy))
+            .map(|pos| (denial_score(game, pos), pos))
+            .filter(|&(score, _)| score >= SIEGE_RECYCLER_MIN_SCORE)
+            .max_by_key(|&(score, pos)| (score, pos.row_col()));
+        if let Some((score, at)) = siege_target.filter(|_| ctx.budget.try_reserve(RECYCLER_COST)) {
+            eprintln!("SIEGE RECYCLER: {:?}, score: {}", at, score);
+            ctx.built.push(at);
+            ctx.actions.push(Action::Build { at });
+        }
+    }
+
+    // Units spawned behind the frontier last turn step onto the frontier tile they were spawned
+    // for, while it is still mine and not about to turn to grass, before the move planner sends
+    // them anywhere else
+    fn honour_commitments(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        for commitment in self.commitments.iter().filter(|commitment| commitment.turn + 1 == game.turn) {
+            let (from, to) = (commitment.from, commitment.to);
+            let dying = game.turns_to_live(to).is_some_and(|turns| turns <= 1);
+            if game.loc(from).owner != Owner::Me || game.loc(to).owner != Owner::Me || !game.passable(to) || dying || ctx.built.contains(&to) {
+                continue;
+            }
+            let Some(amount) = Amount::new(commitment.units.min(game.loc(from).units - ctx.committed[from])) else {
+                continue;
+            };
+            eprintln!("PIPELINED: {} from {:?} to {:?}", amount.get(), from, to);
+            ctx.committed[from] += amount.get();
+            ctx.incoming[to] += amount.get();
+            ctx.actions.push(Action::Move { amount, from, to });
+        }
+    }
+
+    fn move_robots(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        for (from, loc) in game.iter_my_robots() {
+            let n_units = (loc.units - ctx.committed[from]) as usize;
+            if n_units == 0 {
+                continue;
+            }
+            let role = Role::from_units(loc.units);
+            let neighbors: Vec<Pos> = game.neighbors(from)
+                .filter(|&to| game.passable(to) && !ctx.built.contains(&to))
+                .collect();
+            #[cfg(feature = "instrumentation")]
+            eprintln!("MY ROBOTS: {:?}, n_units: {}, role: {:?}, neighbors: {:?}", from, n_units, role, neighbors);
+            // Walled in by grass: the units can only stay
+            let Some(min_dist) = neighbors
+                .iter()
+                .map(|&to| self.path_cost(&role, to))
+                .min()
+            else {
+                continue;
+            };
+            let mut min_dist_destinations = Vec::new();
+            for &to in &neighbors {
+                if self.path_cost(&role, to) == min_dist {
+                    min_dist_destinations.push(to);
+                }
+            }
+            // Full destinations are only used when nothing else is as close
+            let open_destinations: Vec<Pos> = min_dist_destinations
+                .iter()
+                .copied()
+                .filter(|&to| ctx.incoming[to] < max_useful_units(game, to))
+                .collect();
+            if !open_destinations.is_empty() {
+                min_dist_destinations = open_destinations;
+            }
+            // Remainder units, and single scouts, follow the expansion prior first
+            min_dist_destinations.sort_by(|&a, &b| self.expansion_prior(game, from, b).total_cmp(&self.expansion_prior(game, from, a)));
+            if self.config.exploration > 0. && ctx.rng.gen_bool(self.config.exploration) {
+                min_dist_destinations = neighbors;
+                ctx.rng.shuffle(&mut min_dist_destinations);
+            }
+            #[cfg(feature = "instrumentation")]
+            eprintln!("min_dist: {}, min_dist_destinations: {:?}", min_dist, min_dist_destinations);
+            let mut moved = 0;
+            for (k, &to) in min_dist_destinations.iter().enumerate() {
+                let share = n_units / min_dist_destinations.len() + if k < n_units % min_dist_destinations.len() {1} else {0};
+                let Some(amount) = Amount::new(share as i32) else {
+                    break;
+                };
+                moved += share;
+                ctx.incoming[to] += amount.get();
+                ctx.actions.push(Action::Move { amount, from, to });
+            }
+            debug_invariant!(moved == n_units, "moved {} of {} units from {:?}", moved, n_units, from);
+        }
+    }
+
+    // Units thrown onto a defended enemy tile for an even or losing trade pin its defenders for a
+    // turn while the others expand. The move planner never trades down, so each such trade is
+    // looked ahead with the simulator and the best one is played only when its worst case beats
+    // the worst case of the line planned so far.
+    fn sacrifice(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        let mut candidates = Vec::new();
+        for (from, loc) in game.iter_my_robots() {
+            let free = loc.units - ctx.committed[from];
+            for to in game.neighbors(from).filter(|&to| game.passable(to) && game.loc(to).owner == Owner::Enemy) {
+                if let Some(amount) = Amount::new(free.min(game.loc(to).units)) {
+                    candidates.push((from, to, amount));
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return;
+        }
+        // Trades over the most central enemy tiles first, they pin defenders the most ground depends on
+        if candidates.len() > SACRIFICE_MAX_CANDIDATES {
+            if let Some(centrality) = ctx.analysis.centrality() {
+                candidates.sort_by_key(|&(_, to, _)| std::cmp::Reverse(centrality[to]));
+            }
+            candidates.truncate(SACRIFICE_MAX_CANDIDATES);
+        }
+        let defended: Vec<Pos> = candidates.iter().map(|&(_, to, _)| to).collect();
+        let replies = search::enemy_replies(game, &defended);
+        let mut board = game.clone();
+        let table = &mut self.search_table.borrow_mut();
+        let planned = search::worst_case(&mut board, &ctx.actions, &replies, table);
+        let best = candidates
+            .into_iter()
+            .map(|(from, to, amount)| {
+                let (mut actions, mut incoming) = (ctx.actions.clone(), ctx.incoming.clone());
+                divert(&mut actions, &mut incoming, from, to, amount);
+                (search::worst_case(&mut board, &actions, &replies, table), actions, incoming, from, to, amount)
+            })
+            .max_by_key(|candidate| candidate.0);
+        if let Some((value, actions, incoming, from, to, amount)) = best.filter(|candidate| candidate.0 > planned) {
+            eprintln!("SACRIFICE: {} from {:?} onto {:?}, worst case {} instead of {}", amount.get(), from, to, value, planned);
+            ctx.actions = actions;
+            ctx.incoming = incoming;
+        }
+    }
+
+    fn spawn_robots(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        let mut frontier: Vec<Pos> = Vec::new();
+        // One step behind the frontier, including frontier tiles too short-lived to spawn on
+        let mut behind: Vec<Pos> = Vec::new();
+        for (pos, loc) in game.iter_owned(Owner::Me) {
+            if !loc.can_spawn || ctx.built.contains(&pos) {
+                continue;
+            }
+            if game.turns_to_live(pos).is_some_and(|turns| turns < SPAWN_MIN_TILE_LIFE) {
+                continue;
+            }
+            if game.borders_outside(pos) {
+                frontier.push(pos);
+            }
+            else if game.neighbors(pos).any(|next| game.loc(next).owner == Owner::Me && game.borders_outside(next)) {
+                behind.push(pos);
+            }
+        }
+        // Spawns never exceed the free useful slots of the whole front, including front tiles
+        // that cannot take a spawn themselves but can be fed from behind; the rest is banked as
+        // the defensive reserve
+        let capacity: i32 = game.iter_frontier()
+            .filter(|(pos, _)| !ctx.built.contains(pos))
+            .map(|(pos, _)| (max_useful_units(game, pos) - ctx.committed[pos] - ctx.incoming[pos]).max(0))
+            .sum();
+        let n_spawns = if frontier.is_empty() && behind.is_empty() { 0 } else { ctx.budget.affordable_units().min(capacity) };
+        if n_spawns > 0 {
+            let voronoi = ctx.analysis.voronoi();
+            for _ in 0..n_spawns {
+                let open: Vec<Pos> = frontier
+                    .iter()
+                    .copied()
+                    .filter(|&pos| ctx.committed[pos] + ctx.incoming[pos] < max_useful_units(game, pos))
+                    .collect();
+                // With the frontier saturated, units spawned behind it step forward next turn
+                let spawning_behind = open.is_empty() && !behind.is_empty();
+                let candidates = if spawning_behind { &behind } else { &open };
+                if candidates.is_empty() || !ctx.budget.try_reserve(UNIT_COST) {
+                    break;
+                }
+                let weights: Vec<i32> = candidates.iter().map(|&pos| spawn_weight(game, voronoi, pos).max(1)).collect();
+                let mut roll = ctx.rng.gen_range(0..weights.iter().sum::<i32>());
+                let mut k = 0;
+                while roll >= weights[k] {
+                    roll -= weights[k];
+                    k += 1;
+                }
+                let at = candidates[k];
+                ctx.incoming[at] += 1;
+                ctx.actions.push(Action::Spawn { amount: Amount::ONE, at });
+                // Behind the frontier, the unit is committed to the frontier tile next to it with the most room left
+                let step = game.neighbors(at)
+                    .filter(|&to| frontier.contains(&to))
+                    .max_by_key(|&to| max_useful_units(game, to) - ctx.committed[to] - ctx.incoming[to]);
+                if let Some(to) = step.filter(|_| spawning_behind) {
+                    match ctx.commitments.iter_mut().find(|commitment| commitment.from == at && commitment.to == to) {
+                        Some(commitment) => commitment.units += 1,
+                        None => ctx.commitments.push(Commitment { from: at, to, units: 1, turn: game.turn }),
+                    }
+                }
+            }
+        }
+        ctx.budget.earmark(ctx.budget.affordable_units() * UNIT_COST);
+        // Banking only defers a spawn if the tile is still mine next turn: a frontier tile the
+        // adjacent enemy units outnumber, plus the margin, is held now with banked matter when it is enough
+        let (outgoing, _) = unit_flows(game, &ctx.actions);
+        let mut at_risk: Vec<(i32, Pos)> = frontier
+            .iter()
+            .map(|&pos| {
+                let attackers = adjacent_enemy_units(game, pos) + self.defense_margin;
+                (attackers - (game.loc(pos).units - outgoing[pos] + ctx.incoming[pos]), pos)
+            })
+            .filter(|&(deficit, _)| deficit > 0)
+            .collect();
+        at_risk.sort_by_key(|&(deficit, _)| deficit);
+        for (deficit, at) in at_risk {
+            let Some(amount) = Amount::new(deficit).filter(|_| ctx.budget.try_reserve_earmarked(UNIT_COST * deficit)) else {
+                break;
+            };
+            eprintln!("SPEND NOW: {} spawns to hold {:?}", deficit, at);
+            ctx.incoming[at] += deficit;
+            ctx.actions.push(Action::Spawn { amount, at });
+        }
+        if ctx.budget.saved() > 0 {
+            eprintln!("WALL SAVINGS: {} matter", ctx.budget.saved());
+        }
+        let banked = ctx.budget.earmarked();
+        if banked > 0 {
+            eprintln!("BANKED: {} spawns ({} matter), front capacity {}", banked / UNIT_COST, banked, capacity);
+        }
+    }
+
+    // Nothing the referee would reject gets printed, the matter of dropped actions goes back to the budget
+    fn validate(&self, ctx: &mut TurnContext) {
+        let game = ctx.game;
+        let budget = &mut ctx.budget;
+        ctx.actions.retain(|action| match game.validate_after(action, budget.spent()) {
+            Ok(()) => {
+                budget.spend(action_cost(action));
+                true
+            }
+            Err(err) => {
+                debug_invariant!(false, "illegal action {}: {}", action, err);
+                budget.release(action_cost(action));
+                false
+            }
+        });
+        debug_invariant!(budget.reserved() == 0, "{} matter reserved but never planned", budget.reserved());
+        // Said out loud, so a turn with nothing to do is not only a message
+        if ctx.actions.is_empty() {
+            ctx.actions.push(Action::Wait);
+        }
+    }
+
+    fn add_message(&self, ctx: &mut TurnContext) {
+        if let Some(text) = self.status_message(ctx.game, ctx.budget.earmarked()) {
+            ctx.actions.push(Action::Message { text });
+        }
+    }
+
+    fn plan_from_left(&mut self, state: &GameState) -> Vec<Action> {
+        let analysis = Analysis::with_budget(state, self.config.analysis_budget_ms);
+        self.update(state, &analysis);
+        self.search_table.get_mut().new_search();
+        let rng = self.rng.fork();
+        let (actions, commitments) = Planner::run(self, state, &analysis, rng);
+        self.commitments = commitments;
+        self.predict_next_turn(state, &actions);
+        actions
+    }
+
+    fn status_message(&self, game: &GameState, banked: i32) -> Option<String> {
+        let mut composer = MessageComposer::default();
+        if game.turn == 1 {
+            composer.push(MessagePriority::Always, format!("{} c{:08x} t{}", STRATEGY_NAME, self.config.fingerprint(), BUILD_TIMESTAMP.unwrap_or("unknown")));
+        }
+        if self.greedy_mode() {
+            composer.push(MessagePriority::Always, "GREEDY".to_string());
+        }
+        if self.siege_target.is_some() {
+            composer.push(MessagePriority::Always, "SIEGE".to_string());
+        }
+        if !self.wall.is_empty() {
+            composer.push(MessagePriority::Always, "WALL".to_string());
+        }
+        composer.push(MessagePriority::Rotating, format!("u{}/{}", game.count_units(Owner::Me), game.count_units(Owner::Enemy)));
+        composer.push(MessagePriority::Rotating, format!("m{}/{}", game.my_matter, game.enemy_matter));
+        if banked > 0 {
+            composer.push(MessagePriority::Rotating, format!("b{}", banked));
+        }
+        if self.defense_margin > 0 {
+            composer.push(MessagePriority::Rotating, format!("d{}", self.defense_margin));
+        }
+        if let Some(accuracy) = (100 * self.flips_happened_total).checked_div(self.flips_predicted_total) {
+            composer.push(MessagePriority::Rotating, format!("f{}%", accuracy));
+        }
+        composer.compose(game.turn)
+    }
+}
+
+impl Strategy for GreedyOutsideStrategy {
+    // Planned from the left whatever my side: on the right the board is turned around its center
+    // first and the actions back after, so ties between tiles break the same way for both sides
+    // and a side is never favored by the row-major and direction orders
+    fn plan(&mut self, state: &GameState) -> Vec<Action> {
+        if state.my_side != Some(Side::Right) {
+            return self.plan_from_left(state);
+        }
+        if state.turn == 1 {
+            eprintln!("MIRRORED: planning from the left, positions in these logs are turned around");
+        }
+        let (width, height) = (state.width(), state.height());
+        self.plan_from_left(&state.mirrored()).iter().map(|action| action.mirrored(width, height)).collect()
+    }
+}
+
+fn adjacent_enemy_units(game: &GameState, pos: Pos) -> i32 {
+    game.neighbors(pos)
+        .filter(|&next| game.loc(next).owner == Owner::Enemy)
+        .map(|next| game.loc(next).units)
+        .sum()
+}
+
+// Units leaving and arriving on each tile with the turn's moves and spawns
+fn unit_flows(game: &GameState, actions: &[Action]) -> (Grid<i32>, Grid<i32>) {
+    let mut outgoing = Grid::new(game.width(), game.height(), 0);
+    let mut incoming = Grid::new(game.width(), game.height(), 0);
+    for action in actions {
+        match *action {
+            Action::Move { amount, from, to } => {
+                let Some(next) = game.engine_step_toward(from, to) else { continue };
+                outgoing[from] += amount.get();
+                incoming[next] += amount.get();
+            }
+            Action::Spawn { amount, at } => incoming[at] += amount.get(),
+            _ => (),
+        }
+    }
+    (outgoing, incoming)
+}
+
+// Sends `amount` units of `from` onto `to` instead of where the last moves planned from the tile
+// took them. The move planner gives every free unit a move, so those moves hold enough units.
+fn divert(actions: &mut Vec<Action>, incoming: &mut Grid<i32>, from: Pos, to: Pos, amount: Amount) {
+    let mut needed = amount.get();
+    for k in (0..actions.len()).rev() {
+        let Action::Move { amount: planned, from: planned_from, to: planned_to } = actions[k] else { continue };
+        if planned_from != from || needed == 0 {
+            continue;
+        }
+        let taken = needed.min(planned.get());
+        needed -= taken;
+        incoming[planned_to] -= taken;
+        match Amount::new(planned.get() - taken) {
+            Some(rest) => actions[k] = Action::Move { amount: rest, from, to: planned_to },
+            None => {
+                actions.remove(k);
+            }
+        }
+    }
+    debug_invariant!(needed == 0, "{} of {} units to divert from {:?} were not planned", needed, amount.get(), from);
+    incoming[to] += amount.get();
+    actions.push(Action::Move { amount, from, to });
+}
+
+fn action_cost(action: &Action) -> i32 {
+    match action {
+        Action::Spawn { amount, .. } => UNIT_COST * amount.get(),
+        Action::Build { .. } => RECYCLER_COST,
+        _ => 0,
+    }
+}
+
+// The tiles nearest the middle on my side that cut my units off from the enemy's, none if no
+// such wall exists. A wall holds at most WALL_MAX_TILES tiles and under half the map's height, and
+// leaves at least as many tiles on my side as on theirs. Each of its tiles is anchored on the map
+// edge or on grass, so units cannot walk around its ends: a wall can bend across columns, and only
+// anchored tiles are tried. There is nothing to wall off either when my units cannot reach the
+// enemy's in the first place.
+fn corridor_wall(game: &GameState, regions: &Grid<Option<usize>>) -> Vec<Pos> {
+    // Columns are counted from my side
+    let column = |pos: Pos| game.normalized_column(pos.x());
+    // My side of the map without the wall's tiles, from my tiles behind the wall: units already
+    // past it are not walled off
+    let reach = |wall: &[Pos]| -> Grid<bool> {
+        let behind = wall.iter().map(|&pos| column(pos)).min().unwrap_or(game.width());
+        let mut reached = Grid::new(game.width(), game.height(), false);
+        let mut stack: Vec<Pos> = game.iter_owned(Owner::Me).map(|(pos, _)| pos).filter(|&pos| column(pos) < behind).collect();
+        while let Some(pos) = stack.pop() {
+            if !reached[pos] && game.passable(pos) && !wall.contains(&pos) {
+                reached[pos] = true;
+                stack.extend(game.neighbors(pos));
+            }
+        }
+        reached
+    };
+    let cuts = |wall: &[Pos]| -> bool {
+        let reached = reach(wall);
+        let (mine, theirs) = game.positions()
+            .filter(|&pos| game.passable(pos) && !wall.contains(&pos))
+            .fold((0, 0), |(mine, theirs), pos| if reached[pos] { (mine + 1, theirs) } else { (mine, theirs + 1) });
+        !game.iter_owned(Owner::Enemy).any(|(pos, _)| reached[pos]) && mine >= theirs
+    };
+    let my_regions: Vec<usize> = game.iter_owned(Owner::Me).filter_map(|(pos, _)| regions[pos]).collect();
+    if !game.iter_owned(Owner::Enemy).any(|(pos, _)| regions[pos].is_some_and(|region| my_regions.contains(&region))) {
+        return Vec::new();
+    }
+    let anchored: Vec<Pos> = game.positions()
+        .filter(|&pos| game.passable(pos) && column(pos) <= (game.width() - 1) / 2)
+        .filter(|&pos| game.dist_to_border(pos) == 0 || game.neighbors(pos).any(|next| !game.passable(next)))
+        .collect();
+    let mut walls: Vec<Vec<Pos>> = anchored.iter().map(|&pos| vec![pos]).collect();
+    let mut k = 0;
+    while k < walls.len() {
+        let last = walls[k][walls[k].len() - 1];
+        if walls[k].len() < WALL_MAX_TILES {
+            for &pos in anchored.iter().filter(|&&pos| pos.row_col() > last.row_col()) {
+                let mut wall = walls[k].clone();
+                wall.push(pos);
+                walls.push(wall);
+            }
+        }
+        k += 1;
+    }
+    walls
+        .into_iter()
+        .filter(|wall| 2 * wall.len() < game.height() && cuts(wall))
+        .max_by_key(|wall| (wall.iter().map(|&pos| column(pos)).min(), std::cmp::Reverse(wall.len())))
+        .unwrap_or_default()
+}
+
+// Enemy stacks in small enemy pockets touching my territory, cut off from their own side
+fn infiltrators(game: &GameState) -> Vec<Pos> {
+    let mut visited = Grid::new(game.width(), game.height(), false);
+    let mut infiltrators = Vec::new();
+    for start in game.positions() {
+        if visited[start] || game.loc(start).owner != Owner::Enemy {
+            continue;
+        }
+        visited[start] = true;
+        // The whole pocket is flooded, stopping early would leave the rest of a big one to be
+        // taken for small pockets of its own
+        let mut pocket = vec![start];
+        let mut k = 0;
+        while k < pocket.len() {
+            for next in game.neighbors(pocket[k]) {
+                if !visited[next] && game.loc(next).owner == Owner::Enemy {
+                    visited[next] = true;
+                    pocket.push(next);
+                }
+            }
+            k += 1;
+        }
+        if pocket.len() > INFILTRATOR_MAX_TILES {
+            continue;
+        }
+        let touches_me = pocket
+            .iter()
+            .any(|&pos| game.neighbors(pos).any(|next| game.loc(next).owner == Owner::Me));
+        if touches_me {
+            infiltrators.extend(pocket.into_iter().filter(|&pos| game.loc(pos).units > 0));
+        }
+    }
+    infiltrators
+}
+
+// Units able to do work from the tile in the next two turns: one per capturable neighbor,
+// plus enough to trade with the enemy units standing there
+fn max_useful_units(game: &GameState, pos: Pos) -> i32 {
+    game.neighbors(pos)
+        .map(|next| {
+            let loc = game.loc(next);
+            if loc.owner == Owner::Me || loc.scrap_amount == 0 || loc.recycler {
+                0
+            }
+            else if loc.owner == Owner::Enemy {
+                1 + loc.units
+            }
+            else {
+                1
+            }
+        })
+        .sum()
+}
+
+// Passable neighbors plus room to the map edge, capped: cramped edge tiles score lowest. With the
+// Voronoi diagram at hand, neutral neighbors the enemy is as close to count twice, a unit spawned
+// next to them is one the race for them gets.
+fn spawn_weight(game: &GameState, voronoi: Option<&Grid<Owner>>, pos: Pos) -> i32 {
+    let mobility = game.neighbors(pos).filter(|&next| game.passable(next)).count() as i32;
+    let contested = voronoi.map_or(0, |voronoi| {
+        game.neighbors(pos)
+            .filter(|&next| game.passable(next) && game.loc(next).owner == Owner::Neutral && voronoi[next] != Owner::Me)
+            .count() as i32
+    });
+    mobility + contested + game.dist_to_border(pos).min(2)
+}
+
+// Net tiles a recycler here would turn to grass before exhausting its own tile:
+// enemy tiles count for, mine against
+fn denial_score(game: &GameState, pos: Pos) -> i32 {
+    let lifetime = game.loc(pos).scrap_amount;
+    game.neighbors(pos)
+        .map(|next| {
+            let loc = game.loc(next);
+            if loc.scrap_amount == 0 || loc.scrap_amount > lifetime || loc.recycler {
+                0
+            }
+            else {
+                match loc.owner {
+                    Owner::Enemy => 1,
+                    Owner::Me => -1,
+                    Owner::Neutral => 0,
+                }
+            }
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MessagePriority {
+    Always,
+    Rotating,
+}
+
+#[derive(Default)]
+struct MessageComposer {
+    fields: Vec<(MessagePriority, String)>,
+}
+
+impl MessageComposer {
+    fn push(&mut self, priority: MessagePriority, text: String) {
+        self.fields.push((priority, text));
+    }
+
+    // Always fields go first, rotating ones start at a different field each turn so that
+    // whatever does not fit this turn gets shown on the next ones
+    fn compose(self, turn: usize) -> Option<String> {
+        let (always, rotating): (Vec<_>, Vec<_>) = self.fields
+            .into_iter()
+            .partition(|(priority, _)| *priority == MessagePriority::Always);
+        let offset = if rotating.is_empty() { 0 } else { turn % rotating.len() };
+        let ordered = always
+            .into_iter()
+            .chain(rotating[offset..].iter().cloned())
+            .chain(rotating[..offset].iter().cloned());
+        let mut text = String::new();
+        for (_, field) in ordered {
+            let separator = if text.is_empty() { 0 } else { 1 };
+            if text.len() + separator + field.len() > MESSAGE_MAX_LEN {
+                continue;
+            }
+            if separator == 1 {
+                text.push(' ');
+            }
+            text.push_str(&field);
+        }
+        if text.is_empty() { None } else { Some(text) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::format_actions;
+
+    // Each board drawn from both sides: the right one is the left one turned around its center,
+    // with the owners kept, so the bot starts on the left in one and on the right in the other
+    const OPEN_LEFT: &str = "
+        5m   5m1  6.  5.  7.  5.  5.  .
+        5m3  5M   6.  .   8.  5.  5e  5.
+        5.   5m2  8.  4.  .   6.  5E  5e3
+        5.   9.   5.  5.  5.  5.  5e1 5e
+    ";
+    const OPEN_RIGHT: &str = "
+        5e   5e1  5.   5.   5.   5.   9.   5.
+        5e3  5E   6.   .    4.   8.   5m2  5.
+        5.   5e   5.   8.   .    6.   5M   5m3
+        .    5.   5.   7.   5.   6.   5m1  5m
+    ";
+    const CORRIDOR_LEFT: &str = "
+        5m1 5m  5.  .   .   .   .   5.  5e  5e1
+        5M  5m3 5.  5.  5.  6.  5.  5.  5e2 5E
+        5m  5m  5.  .   .   .   .   5.  5e  5e
+        8.  5.  5.  .   .   .   .   5.  6.  8.
+    ";
+    const CORRIDOR_RIGHT: &str = "
+        8.   6.   5.   .    .    .    .    5.   5.   8.
+        5e   5e   5.   .    .    .    .    5.   5m   5m
+        5E   5e2  5.   5.   6.   5.   5.   5.   5m3  5M
+        5e1  5e   5.   .    .    .    .    5.   5m   5m1
+    ";
+    const TURNS: usize = 6;
+
+    fn board(map: &str) -> GameState {
+        let mut game = GameState::from_ascii(map).unwrap();
+        (game.my_matter, game.enemy_matter) = (30, 30);
+        game
+    }
+
+    // Both orientations played side by side for a few turns, the enemy waiting: every turn the
+    // right board must get the mirror of the left board's actions
+    fn assert_mirrored_play(left_map: &str, right_map: &str) {
+        let (mut left, mut right) = (board(left_map), board(right_map));
+        assert_eq!((left.my_side, right.my_side), (Some(Side::Left), Some(Side::Right)));
+        let config = Config { seed: Some(7), ..Config::default() };
+        let mut strategies = [&left, &right].map(|game| GreedyOutsideStrategy::new(game, config));
+        for _ in 0..TURNS {
+            let (width, height) = (left.width(), left.height());
+            assert!(right.grid == left.mirrored().grid && right.my_matter == left.my_matter, "turn {}: the boards are no longer mirrors", left.turn);
+            let left_actions = strategies[0].plan(&left);
+            let right_actions = strategies[1].plan(&right);
+            let turned_back: Vec<Action> = right_actions.iter().map(|action| action.mirrored(width, height)).collect();
+            assert_eq!(format_actions(&turned_back), format_actions(&left_actions), "turn {}", left.turn);
+            left = left.simulate(&left_actions, &[]);
+            right = right.simulate(&right_actions, &[]);
+        }
+    }
+
+    // Boards that used to crash the planner
+    const WALLED_IN: &str = "
+        .    .    .    .    .
+        .    5m2  .    5e1  .
+        .    .    .    .    .
+    ";
+    const NO_FRONTIER: &str = "
+        5m1  5m   .    5e   5e1
+        5m   5m3  .    5e2  5e
+    ";
+
+    // The line the bot would print on the board, which must be legal
+    fn line_on(map: &str) -> String {
+        let game = GameState::from_ascii(map).unwrap();
+        let actions = GreedyOutsideStrategy::new(&game, Config { seed: Some(7), ..Config::default() }).plan(&game);
+        for action in actions.iter() {
+            assert_eq!(game.validate_after(action, 0), Ok(()), "{}", action);
+        }
+        format_actions(&actions)
+    }
+
+    #[test]
+    fn a_stack_walled_in_by_grass_waits() {
+        let line = line_on(WALLED_IN);
+        assert!(line.starts_with("WAIT;MESSAGE "), "{}", line);
+    }
+
+    #[test]
+    fn no_tile_left_to_take_still_gives_a_legal_line() {
+        let line = line_on(NO_FRONTIER);
+        let actions = crate::actions::parse_actions(&line).unwrap();
+        assert!(actions.iter().any(|action| !matches!(action, Action::Message { .. })), "{}", line);
+    }
+
+    #[test]
+    fn plays_the_open_map_the_same_from_both_sides() {
+        assert_mirrored_play(OPEN_LEFT, OPEN_RIGHT);
+    }
+
+    #[test]
+    fn plays_the_corridor_map_the_same_from_both_sides() {
+        assert_mirrored_play(CORRIDOR_LEFT, CORRIDOR_RIGHT);
+    }
+}
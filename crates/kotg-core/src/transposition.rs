@@ -0,0 +1,65 @@
+// Search results cached by the Zobrist hash of the position they were computed for, so a
+// position reached again, by the same actions in another order for one, is not searched twice.
+// The table never grows: each hash has a single slot, and when two results want it the deeper
+// one is kept. Entries from an earlier search are replaced whatever their depth,
+// the board they were computed on is gone after a turn.
+use crate::actions::Action;
+
+#[derive(Debug)]
+pub struct Entry {
+    // The whole hash, slots are picked with its low bits only
+    pub hash: u64,
+    // Turns searched below the position
+    pub depth: u32,
+    pub eval: i32,
+    // My actions the search found best there
+    pub best: Vec<Action>,
+    generation: u32,
+}
+
+#[derive(Debug)]
+pub struct TranspositionTable {
+    slots: Vec<Option<Entry>>,
+    generation: u32,
+}
+
+impl TranspositionTable {
+    // At least `capacity` slots, rounded up to a power of two so a slot is a mask away
+    pub fn new(capacity: usize) -> Self {
+        let n_slots = capacity.max(1).next_power_of_two();
+        TranspositionTable { slots: (0..n_slots).map(|_| None).collect(), generation: 0 }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        hash as usize & (self.slots.len() - 1)
+    }
+
+    // To call before each search: what is already stored ages and gives way to new entries
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<&Entry> {
+        self.slots[self.slot(hash)].as_ref().filter(|entry| entry.hash == hash)
+    }
+
+    // Stores the result unless the slot holds a deeper result of this search
+    pub fn store(&mut self, hash: u64, depth: u32, eval: i32, best: Vec<Action>) {
+        let generation = self.generation;
+        let slot = self.slot(hash);
+        let keep = self.slots[slot]
+            .as_ref()
+            .is_some_and(|entry| entry.generation == generation && entry.depth > depth);
+        if !keep {
+            self.slots[slot] = Some(Entry { hash, depth, eval, best, generation });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+}
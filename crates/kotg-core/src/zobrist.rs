@@ -0,0 +1,43 @@
+// Zobrist hashing: a state's hash is the xor of one key per tile feature value and per matter
+// count, so changing a value only xors its old key out and its new one in. Keys come from a fixed
+// seed and are computed when needed rather than read from a table, which would need a bound on
+// units and matter. Only what the referee sends each turn is hashed: the derived tile flags follow
+// from the rest, and the turn counter is left out so the same position on two turns hashes the same.
+use crate::game::{GameState, Location};
+use crate::rng::splitmix64;
+
+const SEED: u64 = 0x6B6F_7467_7A6F_6272;
+
+#[derive(Debug, Clone, Copy)]
+enum Feature {
+    Scrap,
+    Owner,
+    Units,
+    Recycler,
+    MyMatter,
+    EnemyMatter,
+}
+
+fn key(cell: usize, feature: Feature, value: i32) -> u64 {
+    splitmix64(SEED ^ ((cell as u64) << 35) ^ ((feature as u64) << 32) ^ value as u32 as u64)
+}
+
+// The part of the hash that comes from the tile at row-major index `cell`
+pub fn tile_key(cell: usize, loc: &Location) -> u64 {
+    key(cell, Feature::Scrap, loc.scrap_amount)
+        ^ key(cell, Feature::Owner, loc.owner.to_code())
+        ^ key(cell, Feature::Units, loc.units)
+        ^ key(cell, Feature::Recycler, loc.recycler as i32)
+}
+
+pub fn matter_key(my_matter: i32, enemy_matter: i32) -> u64 {
+    key(0, Feature::MyMatter, my_matter) ^ key(0, Feature::EnemyMatter, enemy_matter)
+}
+
+// The hash computed from scratch, which the incremental one kept by GameState must always equal
+pub fn full_hash(game: &GameState) -> u64 {
+    game.grid
+        .iter()
+        .enumerate()
+        .fold(matter_key(game.my_matter, game.enemy_matter), |hash, (cell, loc)| hash ^ tile_key(cell, loc))
+}
@@ -0,0 +1,143 @@
+// The simulator's invariants on generated boards and action sets, shrunk by proptest to the
+// smallest failing case. simcheck plays whole random games from generated maps; these are single
+// turns from any board the referee could send: small sizes, scrap up to 6, grass, recyclers with
+// no units on them, both players' units and matter. Each player plays a handful of actions, legal
+// or not: moves from anywhere to anywhere, off the map included, builds and spawns on any tile,
+// amounts past what the tile or the matter allows. After simulate:
+// - scrap is never negative and drops by one at most
+// - grass holds no units, no recycler and no owner
+// - matter is what was there, minus the builds and spawns the rules accept, plus the base income
+//   and one for each tile the player's recyclers reach
+// - a tile changes owner only by units standing on it or by turning to grass
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use kotg_core::ascii::parse_map;
+use kotg_core::sim::BASE_INCOME;
+use kotg_core::{format_actions, parse_actions, Action, GameConfig, GameState, Owner, Pos};
+
+const PLAYERS: [Owner; 2] = [Owner::Me, Owner::Enemy];
+const COST: i32 = 10;
+
+// One tile's token as the ascii module reads it
+fn tile() -> impl Strategy<Value = String> {
+    (0..=6i32, 0..3usize, 0..=4i32, any::<bool>()).prop_map(|(scrap, owner, units, recycler)| match (scrap, owner) {
+        (0, _) => ".".to_string(),
+        (_, 0) => format!("{}.", scrap),
+        (_, _) if recycler => format!("{}{}", scrap, if owner == 1 { 'M' } else { 'E' }),
+        (_, _) => format!("{}{}{}", scrap, if owner == 1 { 'm' } else { 'e' }, units),
+    })
+}
+
+// One player's line on a board of that size, coordinates up to one past the map
+fn actions(width: usize, height: usize) -> impl Strategy<Value = Vec<Action>> {
+    let action = (0..3usize, 0..=width, 0..=height, 0..=width, 0..=height, 1..=5i32).prop_map(|(kind, x, y, to_x, to_y, n)| match kind {
+        0 => format!("MOVE {} {} {} {} {}", n, x, y, to_x, to_y),
+        1 => format!("BUILD {} {}", x, y),
+        _ => format!("SPAWN {} {} {}", n, x, y),
+    });
+    vec(action, 0..6).prop_map(|lines| parse_actions(&lines.join(";")).expect("generated actions parse"))
+}
+
+fn board(width: usize, tiles: &[String], my_matter: i32, enemy_matter: i32) -> GameState {
+    let rows: Vec<String> = tiles.chunks(width).map(|row| row.join(" ")).collect();
+    let map = parse_map(rows.iter().map(String::as_str)).expect("generated rows parse");
+    let mut game = GameState::new(&GameConfig { width: map.width, height: map.height });
+    game.update(my_matter, enemy_matter, &map.tiles).expect("generated owners are valid");
+    game
+}
+
+// A board with both players' matter and a line for each
+fn turn() -> impl Strategy<Value = (GameState, Vec<Action>, Vec<Action>)> {
+    (2..=6usize, 1..=4usize).prop_flat_map(|(width, height)| {
+        (vec(tile(), width * height), 0..60i32, 0..60i32, actions(width, height), actions(width, height))
+            .prop_map(move |(tiles, my_matter, enemy_matter, mine, enemy)| (board(width, &tiles, my_matter, enemy_matter), mine, enemy))
+    })
+}
+
+fn passable(game: &GameState, at: Pos) -> bool {
+    at.x() < game.width() && at.y() < game.height() && game.passable(at)
+}
+
+// What player p pays this turn and where their recyclers stand once built, from the rules rather
+// than the simulator: builds first, on their tiles that hold scrap, no recycler and no units, then
+// spawns on their tiles that still hold scrap and no recycler, each while the matter lasts
+fn spending(game: &GameState, p: usize, played: &[Action]) -> (i32, Vec<Pos>) {
+    let mut matter = if p == 0 { game.my_matter } else { game.enemy_matter };
+    let owns = |at: Pos| passable(game, at) && game.loc(at).owner == PLAYERS[p];
+    let mut recyclers: Vec<Pos> = game.iter_owned(PLAYERS[p]).filter(|(_, loc)| loc.recycler).map(|(pos, _)| pos).collect();
+    for action in played {
+        if let Action::Build { at } = *action {
+            if owns(at) && game.loc(at).units == 0 && !recyclers.contains(&at) && matter >= COST {
+                recyclers.push(at);
+                matter -= COST;
+            }
+        }
+    }
+    for action in played {
+        if let Action::Spawn { amount, at } = *action {
+            let cost = COST.saturating_mul(amount.get());
+            if owns(at) && !recyclers.contains(&at) && matter >= cost {
+                matter -= cost;
+            }
+        }
+    }
+    let spent = if p == 0 { game.my_matter } else { game.enemy_matter } - matter;
+    (spent, recyclers)
+}
+
+// The tiles holding scrap on and next to the recyclers
+fn reached(game: &GameState, recyclers: &[Pos]) -> usize {
+    let mut tiles: Vec<Pos> = recyclers
+        .iter()
+        .flat_map(|&pos| std::iter::once(pos).chain(game.neighbors(pos)))
+        .filter(|&pos| game.loc(pos).scrap_amount > 0)
+        .collect();
+    tiles.sort_by_key(|pos| pos.index(game.width()));
+    tiles.dedup();
+    tiles.len()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn scrap_is_never_negative((game, mine, enemy) in turn()) {
+        let after = game.simulate(&mine, &enemy);
+        for pos in game.positions() {
+            let (was, now) = (game.loc(pos).scrap_amount, after.loc(pos).scrap_amount);
+            prop_assert!(now >= 0 && (now == was || now == was - 1), "{:?} scrap went from {} to {}", pos, was, now);
+        }
+    }
+
+    #[test]
+    fn grass_holds_no_units((game, mine, enemy) in turn()) {
+        let after = game.simulate(&mine, &enemy);
+        for (pos, loc) in after.iter_cells().filter(|(_, loc)| loc.scrap_amount == 0) {
+            prop_assert!(loc.units == 0 && !loc.recycler && loc.owner == Owner::Neutral, "{:?} is grass with {:?}", pos, loc);
+        }
+    }
+
+    #[test]
+    fn matter_follows_the_income_rules((game, mine, enemy) in turn()) {
+        let after = game.simulate(&mine, &enemy);
+        for (p, played) in [&mine, &enemy].into_iter().enumerate() {
+            let (spent, recyclers) = spending(&game, p, played);
+            let before = if p == 0 { game.my_matter } else { game.enemy_matter };
+            let expected = before - spent + BASE_INCOME + reached(&game, &recyclers) as i32;
+            let now = if p == 0 { after.my_matter } else { after.enemy_matter };
+            prop_assert_eq!(now, expected, "player {} playing {}", p, format_actions(played));
+        }
+    }
+
+    #[test]
+    fn owners_change_only_under_units_or_to_grass((game, mine, enemy) in turn()) {
+        let after = game.simulate(&mine, &enemy);
+        for pos in game.positions() {
+            let (was, now) = (game.loc(pos), after.loc(pos));
+            if now.owner != was.owner {
+                prop_assert!(now.units > 0 || now.scrap_amount == 0, "{:?} went from {:?} to {:?} with no unit on it", pos, was.owner, now.owner);
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// The crate's check binaries run by `cargo test`, on fewer games than they play by default so the
+// suite stays quick. Run them alone, in release, for the full counts.
+use std::path::Path;
+use std::process::Command;
+
+// Runs the binary from the workspace root, where it finds golden/ and scenarios/, and fails the
+// test with its output when it fails
+fn check(binary: &str, args: &[&str]) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let output = Command::new(binary).args(args).current_dir(root).output().expect("the binary runs");
+    assert!(
+        output.status.success(),
+        "{} {} failed:\n{}",
+        binary,
+        args.join(" "),
+        String::from_utf8_lossy(&output.stdout),
+    );
+}
+
+#[test]
+fn scenarios() {
+    check(env!("CARGO_BIN_EXE_scenario"), &[]);
+}
+
+#[test]
+fn golden_games() {
+    check(env!("CARGO_BIN_EXE_golden"), &[]);
+}
+
+#[test]
+fn simulator_invariants() {
+    check(env!("CARGO_BIN_EXE_simcheck"), &["20"]);
+}
+
+#[test]
+fn mirrored_play() {
+    check(env!("CARGO_BIN_EXE_symmetry"), &["3"]);
+}
+
+#[test]
+fn malformed_input() {
+    check(env!("CARGO_BIN_EXE_fuzz"), &["1000"]);
+}
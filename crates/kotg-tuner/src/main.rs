@@ -0,0 +1,180 @@
+// Searches strategy Config values by scoring them over local games:
+//
+//   cargo run --release -p kotg-tuner -- [--seed N] [--write] [--cold] [rounds] [games] 2>/dev/null
+//
+// A candidate's score is its share of the points against the current Config::default() on the
+// generated maps 0..games, playing each map from both sides, a draw counting half. Each round
+// nudges one parameter of the best Config so far and keeps the result if it scores higher. Bots
+// are seeded the way the arena seeds them, so a game the tuner played replays alone. Their logs
+// go to stderr as in the arena. The nudges come from --seed, else KOTG_SEED as for the bot, else 0,
+// so a run is repeated exactly.
+// With --write a Config that beat the default replaces kotg-core's config_default.rs, which
+// Config::default() and so the submission read. The file goes in with the change it makes to the
+// golden games.
+// Every Config scored is kept by map in a store, see store.rs. A run starts from the stored Config
+// expected to do best on its maps' archetypes, see mapgen::archetype, when playing it again
+// confirms it beats the default, and the rounds nudge it from there. --cold starts from the
+// default as before. The best Config's share on each archetype is printed at the end.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use kotg_core::mapgen::{archetype, MapGenerator, ARCHETYPES};
+use kotg_core::referee::duel;
+use kotg_core::{Config, DirectionalPrior, Rng};
+
+use crate::store::Store;
+
+mod store;
+
+const DEFAULT_ROUNDS: u64 = 20;
+const DEFAULT_GAMES: u64 = 20;
+const SEED_VAR: &str = "KOTG_SEED";
+// Nudges of the directional weights are multiples of this
+const PRIOR_STEP: f64 = 0.25;
+const MAX_DEFENSE_MARGIN: i32 = 6;
+
+// The candidate's share of the points over both sides of every map, each map's result stored
+fn score(candidate: Config, baseline: Config, archetypes: &[&'static str], store: &mut Store) -> f64 {
+    let mut points = 0.;
+    for (seed, &archetype) in archetypes.iter().enumerate() {
+        let duel = duel([candidate, baseline], seed as u64);
+        store.record(baseline, candidate, seed as u64, archetype, duel.points[0]);
+        points += duel.points[0];
+    }
+    points / (2 * archetypes.len()) as f64
+}
+
+// The Config with one parameter moved a step or two
+fn nudge(rng: &mut Rng, config: Config) -> Config {
+    let mut next = config;
+    let steps: i32 = [-2, -1, 1, 2][rng.gen_range(0..4) as usize];
+    let prior = &mut next.expansion_prior;
+    let (min_margin, max_margin) = &mut next.defense_margin_range;
+    match rng.gen_range(0..5) {
+        0 => prior.toward_enemy = (prior.toward_enemy + steps as f64 * PRIOR_STEP).max(0.),
+        1 => prior.toward_center = (prior.toward_center + steps as f64 * PRIOR_STEP).max(0.),
+        2 => prior.along_rows = (prior.along_rows + steps as f64 * PRIOR_STEP).max(0.),
+        3 => *min_margin = (*min_margin + steps.signum()).clamp(0, *max_margin),
+        _ => *max_margin = (*max_margin + steps.signum()).clamp(*min_margin, MAX_DEFENSE_MARGIN),
+    }
+    next
+}
+
+// config_default.rs holding the Config, with how it was found
+fn config_source(config: Config, search_seed: u64, n_games: u64, score: f64) -> String {
+    // Exploration is only for self-play, the submission plays without it
+    let Config { expansion_prior, defense_margin_range, seed: _, exploration: _, analysis_budget_ms: _ } = config;
+    let DirectionalPrior { toward_enemy, toward_center, along_rows } = expansion_prior;
+    format!(
+        "// Written by `kotg-tuner --seed {} --write`: scored {:.3} against the Config before it over {} games.\n\
+         // Run the tuner again rather than editing it.\n\
+         use crate::strategy::{{Config, DirectionalPrior}};\n\
+         \n\
+         pub(crate) const DEFAULT_CONFIG: Config = Config {{\n    \
+             expansion_prior: DirectionalPrior {{ toward_enemy: {:?}, toward_center: {:?}, along_rows: {:?} }},\n    \
+             defense_margin_range: {:?},\n    \
+             seed: None,\n    \
+             exploration: 0.0,\n    \
+             analysis_budget_ms: None,\n\
+         }};\n",
+        search_seed, score, 2 * n_games, toward_enemy, toward_center, along_rows, defense_margin_range,
+    )
+}
+
+// The seed of the search, removed from the arguments
+fn seed(args: &mut Vec<String>) -> Result<u64, String> {
+    let given = match args.iter().position(|arg| arg == "--seed") {
+        Some(k) if k + 1 < args.len() => {
+            args.remove(k);
+            Some(args.remove(k))
+        }
+        Some(_) => return Err("--seed needs a value".to_string()),
+        None => std::env::var(SEED_VAR).ok(),
+    };
+    given.map_or(Ok(0), |seed| seed.trim().parse().map_err(|_| format!("seed {:?} is not a number", seed)))
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let write = args.iter().any(|arg| arg == "--write");
+    let cold = args.iter().any(|arg| arg == "--cold");
+    args.retain(|arg| arg != "--write" && arg != "--cold");
+    let search_seed = match seed(&mut args) {
+        Ok(seed) => seed,
+        Err(err) => {
+            eprintln!("tuner: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let parse = |k: usize, default: u64| args.get(k).map_or(Ok(default), |arg| arg.parse::<u64>().map_err(|_| arg.clone()));
+    let (n_rounds, n_games) = match (parse(0, DEFAULT_ROUNDS), parse(1, DEFAULT_GAMES)) {
+        (Ok(n_rounds), Ok(n_games)) if n_games > 0 => (n_rounds, n_games),
+        (Err(arg), _) | (_, Err(arg)) => {
+            eprintln!("tuner: {:?} is not a number, usage: kotg-tuner [--seed N] [--write] [--cold] [rounds] [games]", arg);
+            return ExitCode::FAILURE;
+        }
+        _ => {
+            eprintln!("tuner: no Config can be scored over 0 games");
+            return ExitCode::FAILURE;
+        }
+    };
+    let baseline = Config::default();
+    let archetypes: Vec<&'static str> = (0..n_games).map(|seed| archetype(&MapGenerator::generate(seed))).collect();
+    let mut store = Store::load();
+    let mut rng = Rng::new(search_seed);
+    let mut best = baseline;
+    let mut best_score = score(best, baseline, &archetypes, &mut store);
+    println!("seed {}, default c{:08x}: {:.3}", search_seed, best.fingerprint(), best_score);
+    // Nudges often land back on a Config already played, by its fingerprint
+    let mut scores = HashMap::from([(best.fingerprint(), best_score)]);
+    if let Some((stored, expected)) = store.best_for(baseline, &archetypes).filter(|&(stored, expected)| !cold && expected > best_score && stored.fingerprint() != best.fingerprint()) {
+        let stored_score = score(stored, baseline, &archetypes, &mut store);
+        scores.insert(stored.fingerprint(), stored_score);
+        let kept = stored_score > best_score;
+        println!("warm start c{:08x}: expected {:.3} from the store, scored {:.3}{}", stored.fingerprint(), expected, stored_score, if kept { ", kept" } else { "" });
+        if kept {
+            best = stored;
+            best_score = stored_score;
+        }
+    }
+    for round in 1..=n_rounds {
+        let candidate = nudge(&mut rng, best);
+        let candidate_score = *scores.entry(candidate.fingerprint()).or_insert_with(|| score(candidate, baseline, &archetypes, &mut store));
+        let kept = candidate_score > best_score;
+        println!("round {} c{:08x}: {:.3}{}", round, candidate.fingerprint(), candidate_score, if kept { ", kept" } else { "" });
+        if kept {
+            best = candidate;
+            best_score = candidate_score;
+        }
+    }
+    println!("best c{:08x}: {:.3} over {} games\n{:?}", best.fingerprint(), best_score, 2 * n_games, best);
+    let shares = store.shares(baseline, best.fingerprint());
+    for archetype in ARCHETYPES {
+        if let Some((share, n_maps)) = shares.get(archetype) {
+            println!("  {}: {:.3} over {} stored maps", archetype, share, n_maps);
+        }
+    }
+    if let Err(err) = store.save() {
+        eprintln!("tuner: {}", err);
+    }
+    if !write {
+        return ExitCode::SUCCESS;
+    }
+    if best.fingerprint() == baseline.fingerprint() {
+        println!("nothing beat the default, config_default.rs is left as it is");
+        return ExitCode::SUCCESS;
+    }
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../kotg-core/src/config_default.rs");
+    match fs::write(&path, config_source(best, search_seed, n_games, best_score)) {
+        Ok(()) => {
+            println!("wrote {}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("tuner: cannot write {}: {}", path.display(), err);
+            ExitCode::FAILURE
+        }
+    }
+}
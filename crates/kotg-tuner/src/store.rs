@@ -0,0 +1,104 @@
+// What every Config the tuner scored did on each map, kept across runs in target/tuner-store.txt so a run can
+// start from the Config that did best on the kinds of maps it plays rather than from the default.
+// A line is one Config on one map against one baseline:
+//
+//   <baseline> <seed> <archetype> <toward_enemy> <toward_center> <along_rows> <min margin> <max margin> <points>
+//
+// the baseline by its fingerprint, the points out of the 2 games of the map. Results against
+// another baseline are kept but not used, shares against different Configs do not add up. A
+// change to the strategy leaves older results in place, which is why a warm start is played again
+// before it is trusted.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use kotg_core::mapgen::ARCHETYPES;
+use kotg_core::{Config, DirectionalPrior};
+
+// An archetype counts for a Config once it played this many of its maps
+const MIN_MAPS: usize = 2;
+
+fn store_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/tuner-store.txt")
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    archetype: &'static str,
+    config: Config,
+    points: f64,
+}
+
+#[derive(Default)]
+pub struct Store {
+    // By the baseline's fingerprint, the Config's and the map's seed
+    entries: HashMap<(u32, u32, u64), Entry>,
+}
+
+fn parse_line(line: &str) -> Option<((u32, u32, u64), Entry)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let &[baseline, seed, archetype, toward_enemy, toward_center, along_rows, min_margin, max_margin, points] = fields.as_slice() else { return None };
+    let expansion_prior = DirectionalPrior { toward_enemy: toward_enemy.parse().ok()?, toward_center: toward_center.parse().ok()?, along_rows: along_rows.parse().ok()? };
+    let config = Config { expansion_prior, defense_margin_range: (min_margin.parse().ok()?, max_margin.parse().ok()?), ..Config::default() };
+    let entry = Entry { archetype: ARCHETYPES.into_iter().find(|&known| known == archetype)?, config, points: points.parse().ok()? };
+    Some(((u32::from_str_radix(baseline, 16).ok()?, config.fingerprint(), seed.parse().ok()?), entry))
+}
+
+impl Store {
+    // The store as the last run left it, empty when there is none
+    pub fn load() -> Store {
+        let text = fs::read_to_string(store_path()).unwrap_or_default();
+        Store { entries: text.lines().filter_map(parse_line).collect() }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(&(baseline, _, seed), entry)| {
+                let DirectionalPrior { toward_enemy, toward_center, along_rows } = entry.config.expansion_prior;
+                let (min_margin, max_margin) = entry.config.defense_margin_range;
+                format!("{:08x} {} {} {:?} {:?} {:?} {} {} {}\n", baseline, seed, entry.archetype, toward_enemy, toward_center, along_rows, min_margin, max_margin, entry.points)
+            })
+            .collect();
+        lines.sort();
+        fs::write(store_path(), lines.concat()).map_err(|err| format!("cannot write {}: {}", store_path().display(), err))
+    }
+
+    // What the Config scored on the map against the baseline, replacing an older result
+    pub fn record(&mut self, baseline: Config, config: Config, seed: u64, archetype: &'static str, points: f64) {
+        self.entries.insert((baseline.fingerprint(), config.fingerprint(), seed), Entry { archetype, config, points });
+    }
+
+    // The Config's share of the points on each archetype against the baseline, with the number of
+    // maps it comes from
+    pub fn shares(&self, baseline: Config, fingerprint: u32) -> HashMap<&'static str, (f64, usize)> {
+        let mut shares: HashMap<&'static str, (f64, usize)> = HashMap::new();
+        for (_, entry) in self.entries.iter().filter(|((against, config, _), _)| *against == baseline.fingerprint() && *config == fingerprint) {
+            let (points, n_maps) = shares.entry(entry.archetype).or_default();
+            *points += entry.points;
+            *n_maps += 1;
+        }
+        shares.into_iter().map(|(archetype, (points, n_maps))| (archetype, (points / (2 * n_maps) as f64, n_maps))).collect()
+    }
+
+    // The stored Config expected to score best on maps of these archetypes, weighted by how many
+    // of each there are. On an archetype it played fewer than MIN_MAPS maps of, a Config counts as
+    // even with the baseline.
+    pub fn best_for(&self, baseline: Config, archetypes: &[&str]) -> Option<(Config, f64)> {
+        let mut candidates: Vec<Config> = Vec::new();
+        for ((against, fingerprint, _), entry) in &self.entries {
+            if *against == baseline.fingerprint() && !candidates.iter().any(|known| known.fingerprint() == *fingerprint) {
+                candidates.push(entry.config);
+            }
+        }
+        candidates
+            .into_iter()
+            .map(|config| {
+                let shares = self.shares(baseline, config.fingerprint());
+                let expected: f64 = archetypes.iter().map(|archetype| shares.get(archetype).filter(|&&(_, n_maps)| n_maps >= MIN_MAPS).map_or(0.5, |&(share, _)| share)).sum();
+                (config, expected / archetypes.len() as f64)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.fingerprint().cmp(&a.0.fingerprint())))
+    }
+}